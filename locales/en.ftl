@@ -0,0 +1,119 @@
+# Messages shown to the user. To add a locale, copy this file to `<lang>.ftl`
+# (e.g. `fr.ftl`), translate the values, and register it in `src/i18n.rs`.
+
+parcel-not-found = Parcel `{ $name }` not found. Did you mean: { $suggestions }
+parcel-ambiguous = `{ $name }` matches several parcels: { $matches }
+parcel-opened = Opened parcel `{ $name }`
+doctor-missing = { $program } is missing, used by { $used_by }: { $install }
+doctor-handler-missing = { $program } is missing, used by the { $prefix } handler
+doctor-unhealthy = Some tools are missing
+entry-app-not-found = The application { $name } could not be found
+entry-file-missing = { $path } does not exist
+entry-url-failed = { $url } is not a URL Launch Services can open
+entry-open-failed = Could not open { $entry }
+which-app-unsupported = { $entry } is neither a file nor a URL
+which-app-none = No application opens { $entry }
+which-none = No parcel contains { $entry }
+info-entries = Entries: { $count } ({ $kinds })
+info-last-opened = Last opened: { $ago } ago
+info-never-opened = Never opened
+info-opens = Opened { $count } times, { $failed } of which failed
+info-average = Average duration: { $duration }
+info-valid = Valid
+info-invalid = Invalid:
+info-missing-path = { $path } doesn't exist
+info-invalid-schedule = the schedule is invalid: { $reason }
+spotlight-no-results = Nothing matches { $query }
+spotlight-choose = Number of the document to open:
+entry-failed = { $entry } failed: { $reason }
+entry-failed-optional = Warning: optional { $entry } failed: { $reason }
+entry-panicked = opening it panicked
+parcel-failed = Failed to open { $count } entries of `{ $name }`
+parcel-open-failed = `{ $name }` failed: { $reason }
+parcels-failed = Failed to open { $count } of the { $total } parcels chosen
+entry-skipped = Skipped { $entry }
+entry-missing = the path doesn't exist
+entry-missing-skipped = Warning: skipped { $entry }, as the path doesn't exist
+parcel-aborted = Stopped opening `{ $name }`
+entry-timed-out = timed out after { $timeout }
+wait-timed-out = { $entry } was not ready in time
+stage-skipped = Skipped [{ $stage }], as some entries failed
+parcel-skipped = Skipped parcel `{ $name }`, as `{ $when }` is false
+no-parcels = No parcels available. Please add parcels to the configuration file.
+no-parcel-selected = No parcel selected.
+no-entries-selected = No entries selected.
+prompt-parcel = Select a parcel to open
+prompt-entries = Select entries of `{ $name }` to open
+fzf-failed = fzf failed with status: { $status }
+gui-chooser-failed = The chooser dialog failed: { $reason }
+chooser-unavailable = None of the choosers of `settings.chooser` can be shown here
+builtin-choose = Number of the one to open:
+builtin-choose-many = Numbers of the ones to open, separated by spaces:
+plan-one-at-a-time = (one at a time)
+plan-at-once = (up to { $limit } at once)
+plan-timeout = (times out after { $timeout })
+entry-cancelled = Cancelled { $entry }
+parcel-interrupted = Interrupted, the remaining entries were not opened
+interrupted-close = Close the { $count } entries opened so far? [y/N]
+launches-unverified = Verified { $verified } of { $total } app launches, these apps were not seen running:
+entries-resumed = Skipped { $count } entries opened by the interrupted open
+entries-already-open = Skipped { $count } entries already open:
+stop-none = No processes started by `{ $name }` are running.
+stop-done = Stopped { $count } processes started by `{ $name }`
+history-failed = Could not record the open in the history: { $reason }
+db-vacuumed = Vacuumed the database at { $path }
+convert-done = Wrote the converted config to { $path }
+import-added = Imported `{ $name }`
+import-skipped = Skipped `{ $name }`, the config already has a parcel with this name
+import-overwritten = Replaced `{ $name }` with the imported parcel
+import-renamed = Imported `{ $name }` as `{ $renamed }`
+import-identical = Skipped `{ $name }`, the config already has the same parcel
+import-merged = Added the imported entries of `{ $name }` to it
+import-conflict = `{ $name }` already exists with different entries
+import-choose = Choose what to do [1]:
+import-keep-mine = Keep mine
+import-take-theirs = Take theirs
+import-merge-entries = Merge the entries
+import-rename = Import it under another name
+parcel-already-opening = `{ $name }` is already being opened
+raise-none = None of the apps of `{ $name }` are running.
+last-none = No parcel was opened yet
+open-no-name = No parcel to open, pass its name or set `settings.default_parcel`
+import-nothing = No parcels were imported from { $path }
+import-toml = Parcels can only be imported into a YAML config, not { $path }
+completions-unknown-shell = Could not detect the shell from $SHELL, pass it explicitly
+completions-installed = Installed the completions to { $path }
+completions-rc = Add this line to the rc file of { $shell } to load them: { $line }
+parcel-timed-out = Opening `{ $name }` timed out after { $timeout }
+autostart-added = Parcel `{ $name }` will be opened at login ({ $path })
+autostart-removed = Parcel `{ $name }` will no longer be opened at login
+autostart-none = No parcels are opened at login.
+calendar-no-triggers = No calendar triggers are set in `settings.calendar.triggers`
+calendar-watching = Watching the calendar for { $count } triggers
+calendar-opening = Opening `{ $name }` for { $event }
+shortcut-name = Open Parcel { $name }
+shortcut-signed = Signed { $path }, add it in Shortcuts
+shortcut-sign-failed = Could not sign the shortcut of `{ $name }`: { $status }
+schedule-invalid = Invalid schedule `{ $schedule }`, expected a cron expression like `0 9 * * 1-5`
+schedule-invalid-parcel = The schedule of `{ $name }` is invalid
+schedule-applied = Parcel `{ $name }` will be opened on `{ $schedule }`
+schedule-removed = Parcel `{ $name }` will no longer be opened on a schedule
+schedule-none = No parcels have a schedule.
+schedule-not-applied = (not applied, run `schedule apply`)
+uninstall-nothing = Nothing to remove.
+uninstall-listing = The following will be removed:
+uninstall-confirm = Remove them? [y/N]
+uninstall-aborted = Nothing was removed.
+uninstall-removed = Removed { $path }
+hook-suggestion = This directory has a parcel `{ $name }`, open it with: { $command }
+completion-entries = { $count } entries
+update-available = A new version is available: { $current } → { $latest }
+restore-none = No backups found in { $dir }
+restore-done = Restored the config from { $path }
+config-unreadable = Failed to read the config at { $path }
+config-invalid = Invalid config at { $path }
+config-include-cycle = The config at { $path } includes itself
+when-invalid = Could not evaluate the condition { $expr }
+lua-failed = The script of { $name } failed
+plugin-invalid = Could not load the plugin at { $path }
+plugin-no-command = The plugin returned no command for { $entry }
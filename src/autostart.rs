@@ -0,0 +1,156 @@
+//! Opening parcels at login, through one LaunchAgent per parcel in `~/Library/LaunchAgents`.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::OnceLock,
+};
+
+use crate::utils;
+
+/// Prefix of the labels of the LaunchAgents, followed by the name of the parcel
+const LABEL_PREFIX: &str = "com.github.fruit-bird.kozutsumi.";
+
+pub fn agents_dir() -> PathBuf {
+    PathBuf::from(shellexpand::tilde("~/Library/LaunchAgents").into_owned())
+}
+
+/// Label of the agent of the parcel, its `/` escaped as `%2F` like in the other files named after parcels
+pub fn label(prefix: &str, name: &str) -> String {
+    format!("{}{}", prefix, name.replace('/', "%2F"))
+}
+
+/// Path of the agent with this label
+pub fn plist_path(label: &str) -> PathBuf {
+    agents_dir().join(format!("{}.plist", label))
+}
+
+/// Install a LaunchAgent that opens the parcel at login.
+///
+/// The agent isn't loaded right away, as that would open the parcel immediately.
+pub fn add(name: &str, config_path: &Path) -> io::Result<PathBuf> {
+    let label = label(LABEL_PREFIX, name);
+    let path = plist_path(&label);
+    write_agent(
+        &path,
+        &label,
+        name,
+        config_path,
+        "    <key>RunAtLoad</key>\n    <true/>",
+    )?;
+    Ok(path)
+}
+
+/// Write a LaunchAgent at `path` that opens the parcel when `trigger`, the XML of its launchd keys, says so
+pub fn write_agent(
+    path: &Path,
+    label: &str,
+    name: &str,
+    config_path: &Path,
+    trigger: &str,
+) -> io::Result<()> {
+    let exe = env::current_exe()?;
+    let config_path = config_path.canonicalize()?;
+    let args = [
+        exe.to_string_lossy(),
+        "--config".into(),
+        config_path.to_string_lossy(),
+        "open".into(),
+        name.into(),
+    ];
+
+    let plist = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+{}
+    </array>
+{}
+</dict>
+</plist>
+"#,
+        escape(label),
+        args.iter()
+            .map(|arg| format!("        <string>{}</string>", escape(arg)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        trigger
+    );
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    utils::write_atomic(path, plist)
+}
+
+/// The launchd domain of the current user's agents, `gui/<uid>`
+pub fn gui_domain() -> &'static str {
+    static DOMAIN: OnceLock<String> = OnceLock::new();
+    DOMAIN.get_or_init(|| {
+        let uid = Command::new("id")
+            .arg("-u")
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .unwrap_or_default();
+        format!("gui/{}", uid)
+    })
+}
+
+/// Remove the LaunchAgent of the parcel, unloading it if it is loaded
+pub fn remove(name: &str) -> io::Result<PathBuf> {
+    let label = label(LABEL_PREFIX, name);
+    let path = plist_path(&label);
+    unload(&label);
+    fs::remove_file(&path)?;
+    Ok(path)
+}
+
+/// Unload the LaunchAgent with this label, if it is loaded
+pub fn unload(label: &str) {
+    // Fails when the agent isn't loaded, which is fine
+    let _ = Command::new("launchctl")
+        .arg("bootout")
+        .arg(format!("{}/{}", gui_domain(), label))
+        .output();
+}
+
+/// Names of the parcels that have a LaunchAgent, along with the path of the agent
+pub fn installed() -> io::Result<Vec<(String, PathBuf)>> {
+    installed_with_prefix(LABEL_PREFIX)
+}
+
+/// Names of the parcels that have a LaunchAgent whose label starts with `prefix`, along with the path of the agent
+pub fn installed_with_prefix(prefix: &str) -> io::Result<Vec<(String, PathBuf)>> {
+    let entries = match fs::read_dir(agents_dir()) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut agents = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let name = file_name
+                .strip_prefix(prefix)?
+                .strip_suffix(".plist")?
+                .replace("%2F", "/");
+            Some((name, entry.path()))
+        })
+        .collect::<Vec<_>>();
+    agents.sort();
+    Ok(agents)
+}
+
+/// Escape text for use inside a plist's XML
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
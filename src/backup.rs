@@ -0,0 +1,129 @@
+//! Timestamped copies of the config, taken before the tool rewrites it.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{state, utils};
+
+/// How many backups are kept, older ones are deleted
+const KEEP: usize = 10;
+
+static BACKED_UP: AtomicBool = AtomicBool::new(false);
+
+pub fn backups_dir() -> PathBuf {
+    state::state_dir().join("backups")
+}
+
+/// Back up the config, unless it was already backed up by this process.
+///
+/// To be called before every mutation of the config, so a session of edits can be undone at once.
+pub fn before_mutation(config_path: &Path) -> io::Result<()> {
+    if BACKED_UP.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+    backup(config_path).map(drop)
+}
+
+/// Copy the config to `backups/parcel-<timestamp>.<ext>`, and delete the oldest backups
+pub fn backup(config_path: &Path) -> io::Result<PathBuf> {
+    let dir = backups_dir();
+    fs::create_dir_all(&dir)?;
+
+    let extension = config_path
+        .extension()
+        .map_or("yml".into(), |ext| ext.to_string_lossy());
+    let path = dir.join(format!("parcel-{}.{}", timestamp(), extension));
+    fs::copy(config_path, &path)?;
+
+    let backups = list()?;
+    for old in backups.iter().take(backups.len().saturating_sub(KEEP)) {
+        fs::remove_file(old)?;
+    }
+    Ok(path)
+}
+
+/// Backups, oldest first
+pub fn list() -> io::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(backups_dir()) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut backups = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("parcel-"))
+        })
+        .collect::<Vec<_>>();
+    // Timestamps are zero-padded, so names sort chronologically
+    backups.sort();
+    Ok(backups)
+}
+
+/// Replace the config with a backup, backing up the current config first so this can be undone too
+pub fn restore(config_path: &Path, backup: &Path) -> io::Result<()> {
+    let contents = fs::read(backup)?;
+    if config_path.exists() {
+        before_mutation(config_path)?;
+    }
+    utils::write_atomic(config_path, contents)
+}
+
+/// Current UTC time as `YYYYMMDD-HHMMSS.mmm`, to the millisecond so that backups taken in the same second
+/// don't overwrite each other
+fn timestamp() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = now.as_secs();
+    let (days, time) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}.{:03}",
+        year,
+        month,
+        day,
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60,
+        now.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backups_taken_in_a_row_are_all_kept() {
+        let config = state::state_dir().join("backup-test.yml");
+        fs::create_dir_all(config.parent().unwrap()).unwrap();
+        fs::write(&config, "work: [Zed]\n").unwrap();
+
+        let first = backup(&config).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let second = backup(&config).unwrap();
+        assert_ne!(first, second);
+        assert!(first.exists() && second.exists());
+        let backups = list().unwrap();
+        assert!(backups.ends_with(&[first, second]));
+    }
+}
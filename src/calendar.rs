@@ -0,0 +1,132 @@
+//! The daemon opening parcels ahead of calendar events, as mapped in `settings.calendar`.
+//!
+//! Events are read with icalBuddy, like for `meeting:next`.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    io,
+    process::Command,
+    thread,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::{OpenArgs, ParcelCommands},
+    config::{ParcelConfig, duration},
+    i18n::t,
+    opener::Opener,
+};
+
+/// How long before an event its parcel is opened, when `settings.calendar.lead` isn't set
+const DEFAULT_LEAD: Duration = Duration::from_secs(5 * 60);
+
+/// How often the calendar is checked
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Parcels opened ahead of calendar events, read from `settings.calendar`:
+///
+/// ```yml
+/// settings:
+///   calendar:
+///     lead: 5m
+///     triggers:
+///       standup: standup
+///       1:1: one-on-one
+/// ```
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalendarSettings {
+    /// How long before an event its parcel is opened, e.g. `5m`
+    #[serde(skip_serializing_if = "Option::is_none", with = "duration")]
+    pub lead: Option<Duration>,
+    /// Parcels to open, by text the title of an event contains, ignoring case
+    pub triggers: BTreeMap<String, String>,
+}
+
+/// A timed event of today
+struct Event {
+    title: String,
+    /// Minutes since midnight
+    start: u32,
+}
+
+/// Minutes since midnight, as told by `date`
+fn now() -> io::Result<u32> {
+    let output = Command::new("date").arg("+%H:%M").output()?;
+    Ok(parse_time(String::from_utf8_lossy(&output.stdout).trim()).unwrap_or_default())
+}
+
+/// Minutes since midnight of a time like `09:55`
+fn parse_time(time: &str) -> Option<u32> {
+    let (hours, minutes) = time.split_once(':')?;
+    Some(hours.parse::<u32>().ok()? * 60 + minutes.parse::<u32>().ok()?)
+}
+
+/// The timed events of today, from lines like `Standup @ 09:55 - 10:10`
+fn events() -> io::Result<Vec<Event>> {
+    let output = Command::new("icalBuddy")
+        .args(["-n", "-nc", "-ea", "-b", "", "-ps", "| @ |"])
+        .args(["-iep", "title,datetime", "-po", "title,datetime"])
+        .args(["-df", "", "-tf", "%H:%M", "eventsToday"])
+        .output()?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (title, times) = line.rsplit_once(" @ ")?;
+            let start = times.split(" - ").next()?;
+            Some(Event {
+                title: title.trim().to_string(),
+                start: parse_time(start.trim())?,
+            })
+        })
+        .collect())
+}
+
+/// Check the calendar until stopped, opening the parcel mapped to an event once it starts within the lead time.
+///
+/// Each parcel is opened once per event, and failing to open one doesn't stop the daemon.
+pub fn run(config: &ParcelConfig, opener: &dyn Opener) -> anyhow::Result<()> {
+    let settings = &config.settings.calendar;
+    if settings.triggers.is_empty() {
+        anyhow::bail!(t!("calendar-no-triggers"));
+    }
+    let lead = settings.lead.unwrap_or(DEFAULT_LEAD).as_secs() as u32 / 60;
+    println!(
+        "{}",
+        t!("calendar-watching", count = settings.triggers.len())
+    );
+
+    let mut opened = HashSet::new();
+    let mut last = 0;
+    loop {
+        let now = now()?;
+        // Events of a new day, with the same titles and times as yesterday's, have to be opened again
+        if now < last {
+            opened.clear();
+        }
+        last = now;
+        for event in events()? {
+            if event.start < now || event.start > now + lead {
+                continue;
+            }
+            let title = event.title.to_lowercase();
+            for (pattern, parcel) in &settings.triggers {
+                if !title.contains(&pattern.to_lowercase())
+                    || !opened.insert((event.title.clone(), event.start, parcel.clone()))
+                {
+                    continue;
+                }
+                println!(
+                    "{}",
+                    t!("calendar-opening", name = parcel, event = event.title)
+                );
+                if let Err(e) = ParcelCommands::open(config, parcel, &OpenArgs::default(), opener) {
+                    eprintln!("{:#}", e);
+                }
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
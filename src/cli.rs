@@ -1,10 +1,43 @@
-use std::path::Path;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
 
-use anyhow::Result;
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum, builder::PossibleValuesParser};
+#[cfg(feature = "completions")]
+use clap_complete::engine::{ArgValueCompleter, CompletionCandidate};
+use serde::{Deserialize, Serialize};
 
-use crate::config::{Entry, ParcelConfig};
-use crate::utils;
+#[cfg(feature = "completions")]
+use crate::completions;
+#[cfg(feature = "rhai")]
+use crate::condition;
+#[cfg(feature = "json")]
+use crate::config::tagged;
+use crate::config::{ConfigFormat, Entry, Parcel, ParcelConfig, Settings, duration};
+#[cfg(feature = "lua")]
+use crate::lua;
+#[cfg(feature = "plugins")]
+use crate::plugins;
+use crate::{
+    autostart, backup, calendar, doctor, dotenv, engine,
+    error::ParcelError,
+    graph::{self, GraphFormat},
+    history::{self, History},
+    hook::{self, HookShell},
+    i18n::{self, t},
+    import::{self, Collision},
+    info::Info,
+    opener::{self, Opener},
+    output::{self, Printer},
+    placeholders,
+    plan::Plan,
+    processes, schedule, shortcuts, spotlight,
+    state::Lock,
+    uninstall, update, utils,
+};
 
 /// A tool to open groups of applications, files, folders, and URLs
 #[derive(Debug, Parser)]
@@ -15,39 +48,268 @@ pub struct ParcelCLI {
     /// Override the default config path
     #[clap(short, long, default_value_t = utils::default_config())]
     config: String,
+    /// When to use colors, overriding `settings.colors` [default: auto]
+    #[clap(long, value_enum, global = true)]
+    color: Option<ColorChoice>,
 }
 
-#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+#[derive(Debug, Clone, Copy, ValueEnum, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorChoice {
+    /// Only color output written to a terminal, unless `NO_COLOR` is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Chooser {
     #[default]
     Fzf,
     #[cfg(feature = "dialog")]
     Dialoguer,
+    /// A numbered list, picked from by typing numbers
+    Builtin,
+    /// A native dialog, which works without a terminal, e.g. from hotkeys and shortcuts
+    Gui,
 }
 
 impl ParcelCLI {
     pub fn run(&self) -> anyhow::Result<()> {
-        self.command.run(Path::new(&self.config))
+        let mut config = match ParcelConfig::load(&self.config) {
+            Ok(config) => config,
+            // Restoring has to work when the config is broken, and uninstalling doesn't read it
+            Err(_)
+                if matches!(
+                    self.command,
+                    ParcelCommands::Restore { .. } | ParcelCommands::Uninstall { .. }
+                ) =>
+            {
+                ParcelConfig::default()
+            }
+            // `run` only uses the config for its settings and aliases, `suggest` for its settings,
+            // `doctor` for its handlers, and `which-app` and `db` not at all, so they work without one
+            Err(ParcelError::ConfigNotFound { path, .. })
+                if matches!(
+                    self.command,
+                    ParcelCommands::Run { .. }
+                        | ParcelCommands::WhichApp { .. }
+                        | ParcelCommands::Suggest
+                        | ParcelCommands::Doctor
+                        | ParcelCommands::Db { .. }
+                ) && path == Path::new(&self.config) =>
+            {
+                ParcelConfig::default()
+            }
+            Err(e) => return Err(e.into()),
+        };
+        self.override_settings(&mut config.settings);
+        i18n::init(config.settings.locale.as_deref());
+        #[cfg(feature = "plugins")]
+        let plugins = plugins::Plugins::load(&opener::Workspace)?;
+        #[cfg(feature = "plugins")]
+        let fallback: &dyn Opener = &plugins;
+        #[cfg(not(feature = "plugins"))]
+        let fallback: &dyn Opener = &opener::Workspace;
+        self.command.run(
+            &config,
+            Path::new(&self.config),
+            &opener::Handlers::new(&config.handlers, fallback),
+        )?;
+
+        if config.settings.update_check {
+            update::notice();
+        }
+        Ok(())
+    }
+
+    /// Apply the global flags that take precedence over the config's settings
+    fn override_settings(&self, settings: &mut Settings) {
+        if let Some(color) = self.color {
+            settings.colors = color;
+        }
+    }
+
+    /// Print an error to stderr, styled with the theme of the config if it can be loaded
+    pub fn report(&self, error: &anyhow::Error) {
+        let mut settings = ParcelConfig::load(&self.config)
+            .map(|config| config.settings)
+            .unwrap_or_default();
+        self.override_settings(&mut settings);
+        let color = output::use_color(&settings, &std::io::stderr());
+        eprintln!(
+            "{}",
+            output::paint(&settings.theme.error, &format!("{:#}", error), color)
+        );
     }
 }
 
 #[derive(Debug, Subcommand)]
 pub enum ParcelCommands {
     /// Opens a parcel by name
-    Open { name: String },
+    Open {
+        /// Name of the parcel, `@last` for the one opened most recently, or `-` to read entries from stdin, one per line
+        /// [default: `PARCEL_DEFAULT`, or settings.default_parcel]
+        #[cfg_attr(feature = "completions", clap(add = ArgValueCompleter::new(complete_parcels)))]
+        name: Option<String>,
+        #[clap(flatten)]
+        args: OpenArgs,
+    },
+    /// Opens the parcel opened most recently again
+    Last {
+        #[clap(flatten)]
+        args: OpenArgs,
+    },
+    /// Closes what a parcel opened, for the entries that can be closed
+    Close {
+        /// Name of the parcel
+        #[cfg_attr(feature = "completions", clap(add = ArgValueCompleter::new(complete_parcels)))]
+        name: String,
+    },
+    /// Terminates the background processes started by a parcel's detached entries
+    Stop {
+        /// Name of the parcel
+        #[cfg_attr(feature = "completions", clap(add = ArgValueCompleter::new(complete_parcels)))]
+        name: String,
+    },
     /// Opens a parcel by choosing from a list
     Choose {
-        /// Choose a parcel to open using a fuzzy finder
-        #[clap(long, value_enum, default_value_t)]
-        chooser: Chooser,
+        /// Choose a parcel to open using a fuzzy finder [default: the first available of settings.chooser]
+        #[clap(long, value_enum)]
+        chooser: Option<Chooser>,
         /// Allow multiple selections
         #[clap(long, default_value_t)]
         multi: bool,
+        /// Choose individual entries of this parcel instead of whole parcels
+        #[cfg_attr(feature = "completions", clap(add = ArgValueCompleter::new(complete_parcels)))]
+        #[clap(long, value_name = "PARCEL")]
+        entries: Option<String>,
+    },
+    /// Opens a single entry, parsed like the entries of the config
+    Run {
+        /// The entry to open, e.g. `https://example.com` or `~/Downloads`
+        entry: String,
+        #[clap(flatten)]
+        args: RunArgs,
+    },
+    /// Opens a parcel at login, through a LaunchAgent
+    #[clap(args_conflicts_with_subcommands = true)]
+    Autostart {
+        /// Name of the parcel to open at login
+        #[cfg_attr(feature = "completions", clap(add = ArgValueCompleter::new(complete_parcels)))]
+        name: Option<String>,
+        #[clap(subcommand)]
+        action: Option<AutostartAction>,
+    },
+    /// Opens parcels on the `schedule` of the config, through LaunchAgents
+    Schedule {
+        #[clap(subcommand)]
+        action: ScheduleAction,
+    },
+    /// Adds "Open Parcel" shortcuts to the Shortcuts app
+    Shortcuts {
+        #[clap(subcommand)]
+        action: ShortcutsAction,
+    },
+    /// Restores the config from a backup taken before it was last modified
+    Restore {
+        /// Backup to restore [default: the most recent one]
+        backup: Option<PathBuf>,
+        /// List the available backups instead of restoring one
+        #[clap(long)]
+        list: bool,
+    },
+    /// Removes everything created by the tool, except for the config
+    Uninstall {
+        /// Don't ask for confirmation
+        #[clap(short, long)]
+        yes: bool,
+    },
+    /// Shows a parcel's description, entries by type, how often it was opened, and whether it is valid
+    Info {
+        /// Name of the parcel
+        #[cfg_attr(feature = "completions", clap(add = ArgValueCompleter::new(complete_parcels)))]
+        name: String,
+        /// Output in JSON format, useful for scripting
+        #[cfg(feature = "json")]
+        #[clap(long)]
+        json: bool,
+    },
+    /// Prints the graph linking parcels to their entries, showing the entries they share
+    Graph {
+        /// Format of the graph
+        #[clap(long, value_enum, default_value_t)]
+        format: GraphFormat,
+    },
+    /// Prints a shell snippet suggesting the parcels of a project's `.kozutsumi.yml` when entering it
+    Hook {
+        /// The shell to print the snippet for
+        #[clap(value_enum)]
+        shell: HookShell,
+    },
+    /// Lists the parcels containing an entry, to find where it is used
+    Which {
+        /// The entry, parsed like the entries of the config, or part of it, e.g. `Slack` or `acme`
+        entry: String,
     },
+    /// Prints the application a file or URL entry is opened with
+    WhichApp {
+        /// The entry, e.g. `~/Documents/report.pdf` or `https://example.com`
+        entry: String,
+    },
+    /// Checks that the external tools used by entries, choosers, and handlers are installed
+    Doctor,
+    /// Adds the parcels of another config file to the config
+    Import {
+        /// The config file to import the parcels of
+        path: PathBuf,
+        /// Prefix added to the names of the imported parcels, e.g. `teams/`
+        #[clap(long, default_value = "")]
+        prefix: String,
+        /// What to do when an imported parcel is named like a parcel of the config
+        #[clap(long, value_enum, default_value_t)]
+        on_conflict: Collision,
+    },
+    /// Converts the config to another format, printing it unless written to a file
+    Convert {
+        /// The format to convert the config to
+        #[clap(long, value_enum)]
+        to: ConfigFormat,
+        /// Write the converted config to this file
+        #[clap(long)]
+        out: Option<PathBuf>,
+    },
+    /// Opens the parcels mapped to calendar events in `settings.calendar` ahead of the events, until stopped
+    Daemon,
+    /// Maintains the database of the parcels opened
+    Db {
+        #[clap(subcommand)]
+        action: DbAction,
+    },
+    /// Suggests or opens the parcels of the current directory's `.kozutsumi.yml`, run by the shell hook
+    #[clap(hide = true)]
+    Suggest,
     /// Lists all available parcels
     List {
         /// Name of the parcel to list items for
+        #[cfg_attr(feature = "completions", clap(add = ArgValueCompleter::new(complete_parcels)))]
         name: Option<String>,
+        /// Show every parcel as a tree of its phases and entries, with their counts
+        #[clap(long, conflicts_with = "name")]
+        tree: bool,
+        /// Show only the entries of these types, e.g. `--type url,file`
+        #[clap(
+            long = "type",
+            value_name = "TYPE",
+            value_delimiter = ',',
+            value_parser = PossibleValuesParser::new(Entry::KINDS.iter().copied())
+        )]
+        types: Vec<String>,
+        /// Show the entries as they are opened, with the ones of Lua scripts, and the command opening each
+        #[clap(long, conflicts_with = "tree")]
+        resolve: bool,
         /// Output in JSON format, useful for scripting
         #[cfg(feature = "json")]
         #[clap(long, default_value = "false")]
@@ -56,69 +318,633 @@ pub enum ParcelCommands {
     #[cfg(feature = "completions")]
     /// Generate shell completions
     Completions {
-        /// The shell to generate the completions for
+        /// The shell to generate the completions for [default: the shell of `$SHELL`]
         #[clap(value_enum)]
-        shell: clap_complete::Shell,
+        shell: Option<clap_complete::Shell>,
+        /// Write the completions where the shell loads them from instead of printing them
+        #[clap(long)]
+        install: bool,
+    },
+}
+
+/// Parcel names starting with what was typed, described by their icon and number of entries
+#[cfg(feature = "completions")]
+fn complete_parcels(current: &std::ffi::OsStr) -> Vec<CompletionCandidate> {
+    let Some(current) = current.to_str() else {
+        return Vec::new();
+    };
+    let Ok(config) = ParcelConfig::load(utils::default_config()) else {
+        return Vec::new();
+    };
+
+    let mut parcels = config
+        .parcels
+        .iter()
+        .filter(|(name, _)| name.starts_with(current))
+        .collect::<Vec<_>>();
+    parcels.sort_by_key(|(name, _)| name.as_str());
+    parcels
+        .into_iter()
+        .map(|(name, parcel)| {
+            let count = t!("completion-entries", count = parcel.entries().count());
+            let help = match parcel.text_icon() {
+                Some(icon) => format!("{} {}", icon, count),
+                None => count,
+            };
+            CompletionCandidate::new(name).help(Some(help.into()))
+        })
+        .collect()
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ScheduleAction {
+    /// Installs a LaunchAgent for each parcel with a schedule, and removes the ones of parcels without one
+    Apply,
+    /// Lists the parcels with a schedule, and whether it is applied
+    List,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ShortcutsAction {
+    /// Signs a shortcut opening the parcel, or every parcel, and opens it in Shortcuts to be added
+    Install {
+        /// Name of the parcel [default: every parcel]
+        #[cfg_attr(feature = "completions", clap(add = ArgValueCompleter::new(complete_parcels)))]
+        name: Option<String>,
     },
 }
 
+#[derive(Debug, Subcommand)]
+pub enum DbAction {
+    /// Reclaims the space left unused in the database
+    Vacuum,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AutostartAction {
+    /// Stops opening a parcel at login
+    Remove { name: String },
+    /// Lists the parcels opened at login
+    List,
+}
+
+/// Options controlling how a parcel is opened
+#[derive(Debug, Default, Args)]
+pub struct OpenArgs {
+    #[clap(flatten)]
+    run: RunArgs,
+    /// Only bring the parcel's apps that are already running forward, without opening anything
+    #[clap(long, conflicts_with = "plan")]
+    raise: bool,
+    /// Return only once the parcel's apps have quit and its detached processes have exited
+    #[clap(long, conflicts_with_all = ["plan", "raise"])]
+    wait: bool,
+}
+
+/// Options controlling how entries are opened, by `open` and `run` alike
+#[derive(Debug, Default, Args)]
+pub struct RunArgs {
+    /// Print the resolved plan of what would be opened instead of opening it
+    #[clap(long)]
+    plan: bool,
+    /// Format of the printed plan
+    #[clap(long, value_enum, default_value_t, requires = "plan")]
+    format: PlanFormat,
+    /// Cancel the entries still opening after this long, e.g. `60s` [default: the parcel's `timeout`]
+    #[clap(long, value_parser = duration::parse)]
+    timeout: Option<Duration>,
+    /// Skip the entries already open: running apps, open files, and detached processes still running
+    #[clap(long)]
+    idempotent: bool,
+    /// Skip the entries opened by the last open of the parcel, if it was interrupted or failed
+    #[clap(long, conflicts_with = "plan")]
+    resume: bool,
+    /// Print what happened to each entry once the parcel is opened
+    #[cfg(feature = "json")]
+    #[clap(long, value_enum, conflicts_with = "plan")]
+    report: Option<ReportFormat>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+pub enum PlanFormat {
+    #[default]
+    Text,
+    #[cfg(feature = "json")]
+    Json,
+}
+
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ReportFormat {
+    Json,
+}
+
 impl ParcelCommands {
-    pub fn run(&self, config_path: &Path) -> anyhow::Result<()> {
-        let config = ParcelConfig::load(config_path)?;
+    pub fn run(
+        &self,
+        config: &ParcelConfig,
+        config_path: &Path,
+        opener: &dyn Opener,
+    ) -> anyhow::Result<()> {
         match self {
-            Self::Open { name } => Self::open(&config, name)?,
-            Self::Choose { chooser, multi } => match chooser {
-                Chooser::Fzf => utils::choose_fzf(config_path, *multi)?,
+            Self::Open { name, args } => {
+                let Some(name) = name
+                    .clone()
+                    .or_else(|| dotenv::var("PARCEL_DEFAULT"))
+                    .or_else(|| config.settings.default_parcel.clone())
+                else {
+                    anyhow::bail!(t!("open-no-name"));
+                };
+                Self::open(config, &name, args, opener)?
+            }
+            Self::Last { args } => Self::open(config, "@last", args, opener)?,
+            Self::Close { name } => engine::close(utils::find_parcel(config, name)?.1, opener),
+            Self::Stop { name } => {
+                // Processes are tracked under the full name of the parcel, whatever it was opened as
+                let (name, _) = utils::find_parcel(config, name)?;
+                match processes::stop(name)? {
+                    0 => println!("{}", t!("stop-none", name = name)),
+                    count => println!("{}", t!("stop-done", name = name, count = count)),
+                }
+            }
+            Self::Run { entry, args } => {
+                let mut parsed = entry.parse::<Entry>()?;
+                config.resolve_entry(&mut parsed);
+                let mut parcel = Parcel {
+                    entries: vec![parsed.into()],
+                    ..Default::default()
+                };
+                if !args.plan {
+                    placeholders::fill(config, &mut parcel)?;
+                }
+                Self::open_parcel(config, entry, &parcel, args, opener)?;
+            }
+            Self::Choose {
+                chooser,
+                multi,
+                entries,
+            } => match (
+                match chooser {
+                    Some(chooser) => *chooser,
+                    None => utils::preferred_chooser(&config.settings)?,
+                },
+                entries,
+            ) {
+                (Chooser::Fzf, Some(name)) => utils::choose_entries_fzf(config, name, opener)?,
+                (Chooser::Fzf, None) => utils::choose_fzf(config, config_path, *multi, opener)?,
+                (Chooser::Gui, Some(name)) => utils::choose_entries_gui(config, name, opener)?,
+                (Chooser::Gui, None) => utils::choose_gui(config, *multi, opener)?,
+                (Chooser::Builtin, Some(name)) => {
+                    utils::choose_entries_builtin(config, name, opener)?
+                }
+                (Chooser::Builtin, None) => utils::choose_builtin(config, *multi, opener)?,
                 #[cfg(feature = "dialog")]
-                Chooser::Dialoguer => utils::choose(config_path, *multi)?,
+                (Chooser::Dialoguer, Some(name)) => utils::choose_entries(config, name, opener)?,
+                #[cfg(feature = "dialog")]
+                (Chooser::Dialoguer, None) => utils::choose(config, *multi, opener)?,
             },
 
-            #[cfg(feature = "json")]
-            Self::List { json, .. } if *json => println!("{}", serde_json::to_string(&config)?),
-            Self::List { name: Some(n), .. } => Self::list_parcel(&config, n)?,
-            Self::List { .. } => println!("{}", config),
+            Self::Autostart {
+                name: Some(name), ..
+            } => {
+                let (name, _) = utils::find_parcel(config, name)?;
+                let path = autostart::add(name, config_path)?;
+                println!(
+                    "{}",
+                    t!("autostart-added", name = name, path = path.display())
+                );
+            }
+            Self::Autostart {
+                action: Some(AutostartAction::Remove { name }),
+                ..
+            } => {
+                autostart::remove(name)?;
+                println!("{}", t!("autostart-removed", name = name));
+            }
+            Self::Autostart { .. } => {
+                let agents = autostart::installed()?;
+                if agents.is_empty() {
+                    println!("{}", t!("autostart-none"));
+                }
+                for (name, _) in agents {
+                    println!("{}", name);
+                }
+            }
+
+            Self::Schedule {
+                action: ScheduleAction::Apply,
+            } => schedule::apply(config, config_path)?,
+            Self::Schedule {
+                action: ScheduleAction::List,
+            } => {
+                let installed = schedule::installed()?;
+                let mut scheduled = config
+                    .parcels
+                    .iter()
+                    .filter_map(|(name, parcel)| Some((name, parcel.schedule.as_ref()?)))
+                    .collect::<Vec<_>>();
+                scheduled.sort();
+                if scheduled.is_empty() {
+                    println!("{}", t!("schedule-none"));
+                }
+                for (name, cron) in scheduled {
+                    if installed.iter().any(|(installed, _)| installed == name) {
+                        println!("{}  {}", name, cron);
+                    } else {
+                        println!("{}  {}  {}", name, cron, t!("schedule-not-applied"));
+                    }
+                }
+            }
+            Self::Shortcuts {
+                action: ShortcutsAction::Install { name },
+            } => {
+                let names = match name {
+                    Some(name) => vec![utils::find_parcel(config, name)?.0],
+                    None => {
+                        let mut names = config
+                            .parcels
+                            .keys()
+                            .map(String::as_str)
+                            .collect::<Vec<_>>();
+                        names.sort();
+                        names
+                    }
+                };
+                for path in shortcuts::install(&names, config_path)? {
+                    println!("{}", t!("shortcut-signed", path = path.display()));
+                }
+            }
+            Self::Restore { list: true, .. } => {
+                for backup in backup::list()? {
+                    println!("{}", backup.display());
+                }
+            }
+            Self::Restore { backup: path, .. } => {
+                let Some(path) = path.clone().or_else(|| backup::list().ok()?.pop()) else {
+                    anyhow::bail!(t!("restore-none", dir = backup::backups_dir().display()));
+                };
+                backup::restore(config_path, &path)?;
+                println!("{}", t!("restore-done", path = path.display()));
+            }
+            Self::Uninstall { yes } => uninstall::run(*yes)?,
+            Self::Hook { shell } => print!("{}", hook::snippet(*shell)?),
+            Self::Info { name, .. } => {
+                let info = Info::collect(config, name)?;
+                #[cfg(feature = "json")]
+                if let Self::Info { json: true, .. } = self {
+                    println!("{}", serde_json::to_string(&info)?);
+                    return Ok(());
+                }
+                print!("{}", info);
+            }
+            Self::Graph { format } => print!("{}", graph::render(config, *format)),
+            Self::Suggest => hook::suggest(config, opener)?,
+            Self::Which { entry } => {
+                let found = utils::parcels_containing(config, entry);
+                if found.is_empty() {
+                    anyhow::bail!(t!("which-none", entry = entry));
+                }
+                let printer = Printer::new(&config.settings);
+                let color = output::use_color(&config.settings, &std::io::stdout());
+                for (name, entries) in found {
+                    println!(
+                        "{}",
+                        output::paint(&config.settings.theme.parcel, name, color)
+                    );
+                    for entry in entries {
+                        println!("{}", printer.entry(entry));
+                    }
+                }
+            }
+            Self::WhichApp { entry } => {
+                let parsed = entry.parse::<Entry>()?;
+                if !matches!(parsed, Entry::File(_) | Entry::Url(_)) {
+                    anyhow::bail!(t!("which-app-unsupported", entry = entry));
+                }
+                match opener::default_app(&parsed) {
+                    Some(app) => println!("{}", app),
+                    None => anyhow::bail!(t!("which-app-none", entry = entry)),
+                }
+            }
+            Self::Doctor => {
+                if !doctor::run(config) {
+                    anyhow::bail!(t!("doctor-unhealthy"));
+                }
+            }
+            Self::Import {
+                path,
+                prefix,
+                on_conflict,
+            } => import::run(config, config_path, path, prefix, *on_conflict)?,
+            Self::Convert { to, out } => {
+                let converted = ParcelConfig::convert(config_path, *to)?;
+                match out {
+                    Some(path) => {
+                        utils::write_atomic(path, converted)?;
+                        println!("{}", t!("convert-done", path = path.display()));
+                    }
+                    None => print!("{}", converted),
+                }
+            }
+            Self::Daemon => calendar::run(config, opener)?,
+            Self::Db {
+                action: DbAction::Vacuum,
+            } => {
+                History::open()?.vacuum()?;
+                println!("{}", t!("db-vacuumed", path = history::path().display()));
+            }
+
+            Self::List {
+                name,
+                tree,
+                types,
+                resolve,
+                ..
+            } => {
+                let parcels = Self::listed(config, name.as_deref(), types, *resolve)?;
+                #[cfg(feature = "json")]
+                if let Self::List { json: true, .. } = self {
+                    let json = tagged::scope(|| serde_json::to_string(&parcels))?;
+                    println!("{}", json);
+                    return Ok(());
+                }
+                let printer = Printer::new(&config.settings).resolving(resolve.then_some(opener));
+                if *tree {
+                    print!("{}", printer.tree(&parcels));
+                } else if name.is_some() {
+                    for parcel in parcels.values() {
+                        print!("{}", printer.parcel(parcel));
+                    }
+                } else {
+                    println!("{}", printer.parcels(&parcels));
+                }
+            }
 
             #[cfg(feature = "completions")]
-            Self::Completions { shell } => {
-                let mut cmd = <ParcelCLI as clap::CommandFactory>::command();
-                let name = cmd.get_name().to_string();
-                clap_complete::generate(*shell, &mut cmd, name, &mut std::io::stdout());
+            Self::Completions { shell, install } => {
+                let shell = match shell {
+                    Some(shell) => *shell,
+                    None => completions::detect()?,
+                };
+                if !install {
+                    std::io::Write::write_all(
+                        &mut std::io::stdout(),
+                        &completions::script(shell)?,
+                    )?;
+                    return Ok(());
+                }
+                let (path, rc_line) = completions::install(shell)?;
+                println!("{}", t!("completions-installed", path = path.display()));
+                if let Some(line) = rc_line {
+                    println!("{}", t!("completions-rc", shell = shell, line = line));
+                }
             }
         }
         Ok(())
     }
 
-    fn open(config: &ParcelConfig, name: &str) -> anyhow::Result<()> {
-        config
-            .parcels
-            .get(name)
-            .ok_or_else(|| {
-                anyhow::anyhow!(
-                    "Parcel `{}` not found. Available parcels: {}",
-                    name,
-                    utils::available_parcels(config)
-                )
-            })?
-            .iter()
-            .map(Entry::open)
-            .filter_map(Result::ok)
-            .for_each(|_| { /* Successfully opened an entry */ });
+    pub fn open(
+        config: &ParcelConfig,
+        name: &str,
+        args: &OpenArgs,
+        opener: &dyn Opener,
+    ) -> anyhow::Result<()> {
+        match Self::begin(config, name, args)? {
+            Some(opening) => opening.finish(config, args, opener),
+            None => Ok(()),
+        }
+    }
+
+    /// Find the parcel and ask for everything it needs, without opening it yet,
+    /// or return `None` if there is nothing left to open
+    pub fn begin(
+        config: &ParcelConfig,
+        name: &str,
+        args: &OpenArgs,
+    ) -> anyhow::Result<Option<Opening>> {
+        let last;
+        let name = if name == "@last" {
+            last = History::open()?
+                .last()?
+                .ok_or_else(|| anyhow::anyhow!(t!("last-none")))?;
+            last.as_str()
+        } else {
+            name
+        };
+
+        let stdin_parcel;
+        let (name, parcel) = if name == "-" {
+            stdin_parcel = utils::read_parcel(config, std::io::stdin().lock())?;
+            (name, &stdin_parcel)
+        } else {
+            utils::find_parcel(config, name)?
+        };
+
+        if args.raise {
+            Self::raise(name, parcel)?;
+            return Ok(None);
+        }
+
+        // A double-tapped hotkey or overlapping trigger shouldn't open everything twice
+        let lock = if args.run.plan || name == "-" {
+            None
+        } else {
+            let lock = Lock::try_acquire(Path::new("locks").join(name.replace('/', "%2F")))?;
+            if lock.is_none() {
+                eprintln!("{}", t!("parcel-already-opening", name = name));
+                return Ok(None);
+            }
+            lock
+        };
 
+        let opening = Opening {
+            name: name.to_string(),
+            parcel: Parcel::default(),
+            lock,
+            opened_at: SystemTime::now(),
+            start: Instant::now(),
+        };
+        match Self::prepare(config, name, parcel, args) {
+            Ok(Some(parcel)) => Ok(Some(Opening { parcel, ..opening })),
+            Ok(None) => {
+                opening.record(true, args);
+                Ok(None)
+            }
+            Err(e) => {
+                opening.record(false, args);
+                Err(e)
+            }
+        }
+    }
+
+    /// Bring the running apps of the parcel forward
+    fn raise(name: &str, parcel: &Parcel) -> anyhow::Result<()> {
+        let mut raised = 0;
+        for entry in parcel.entries() {
+            if let Entry::App(app) = entry
+                && opener::raise_app(&app.name)
+            {
+                raised += 1;
+            }
+        }
+        if raised == 0 {
+            println!("{}", t!("raise-none", name = name));
+        }
         Ok(())
     }
 
-    fn list_parcel(config: &ParcelConfig, name: &str) -> anyhow::Result<()> {
-        if let Some(entries) = config.parcels.get(name) {
-            for entry in entries {
-                println!("- {}", entry);
+    /// The parcel as it will be opened, with its placeholders filled and its Spotlight queries
+    /// resolved, or `None` if its condition doesn't hold
+    fn prepare(
+        config: &ParcelConfig,
+        name: &str,
+        parcel: &Parcel,
+        args: &OpenArgs,
+    ) -> anyhow::Result<Option<Parcel>> {
+        #[cfg(feature = "rhai")]
+        if let Some(when) = &parcel.when
+            && !condition::eval(when)?
+        {
+            println!("{}", t!("parcel-skipped", name = name, when = when));
+            return Ok(None);
+        }
+
+        let mut parcel = Self::scripted(config, name, parcel)?.into_owned();
+        // Plans are printed as the entries are written, nothing is asked until the parcel is opened
+        if !args.run.plan {
+            placeholders::fill(config, &mut parcel)?;
+            spotlight::resolve(config, &mut parcel)?;
+        }
+        Ok(Some(parcel))
+    }
+
+    /// Open the parcel, or only print its plan if asked to
+    fn open_parcel(
+        config: &ParcelConfig,
+        name: &str,
+        parcel: &Parcel,
+        args: &RunArgs,
+        opener: &dyn Opener,
+    ) -> anyhow::Result<()> {
+        let mut plan = Plan::new(name, parcel, &config.settings);
+        plan.timeout = args.timeout.or(plan.timeout);
+        plan.idempotent |= args.idempotent;
+        plan.resume = args.resume;
+        if args.plan {
+            match args.format {
+                PlanFormat::Text => print!("{}", plan),
+                #[cfg(feature = "json")]
+                PlanFormat::Json => println!("{}", serde_json::to_string(&plan)?),
             }
-            Ok(())
-        } else {
-            anyhow::bail!(
-                "Parcel `{}` not found. Available parcels: {}",
-                name,
-                utils::available_parcels(config)
-            );
+            return Ok(());
+        }
+
+        #[cfg(feature = "json")]
+        if let Some(ReportFormat::Json) = args.report {
+            let mut results = Vec::new();
+            let opened = engine::run_with_results(&plan, opener, &mut results);
+            println!("{}", serde_json::to_string(&results)?);
+            return opened;
+        }
+        engine::run(&plan, opener)
+    }
+
+    /// The parcel with the entries returned by its Lua script added, as it is opened
+    #[cfg_attr(not(feature = "lua"), allow(unused_variables))]
+    fn scripted<'p>(
+        config: &ParcelConfig,
+        name: &str,
+        parcel: &'p Parcel,
+    ) -> anyhow::Result<Cow<'p, Parcel>> {
+        #[cfg(feature = "lua")]
+        if let Some(source) = &parcel.lua {
+            let mut entries = parcel.entries.clone();
+            entries.extend(lua::entries(config, name, source)?);
+            return Ok(Cow::Owned(Parcel {
+                entries,
+                lua: None,
+                ..parcel.clone()
+            }));
+        }
+        Ok(Cow::Borrowed(parcel))
+    }
+
+    /// The parcels to list, or only the named one, keeping only the entries of the given types if any.
+    /// Parcels left without entries are dropped, unless named.
+    /// Resolved parcels have the entries of their Lua script added
+    fn listed(
+        config: &ParcelConfig,
+        name: Option<&str>,
+        types: &[String],
+        resolve: bool,
+    ) -> anyhow::Result<HashMap<String, Parcel>> {
+        let mut parcels = match name {
+            Some(name) => {
+                let (name, parcel) = utils::find_parcel(config, name)?;
+                HashMap::from([(name.to_string(), parcel.clone())])
+            }
+            None => config.parcels.clone(),
+        };
+        if resolve {
+            for (name, parcel) in &mut parcels {
+                *parcel = Self::scripted(config, name, parcel)?.into_owned();
+            }
+        }
+        if !types.is_empty() {
+            for parcel in parcels.values_mut() {
+                parcel.retain_entries(|entry| types.iter().any(|kind| kind == entry.kind()));
+            }
+            if name.is_none() {
+                parcels.retain(|_, parcel| parcel.entries().next().is_some());
+            }
+        }
+        Ok(parcels)
+    }
+}
+
+/// A parcel that was found and locked, and had everything it needs asked for, ready to be opened
+pub struct Opening {
+    name: String,
+    parcel: Parcel,
+    lock: Option<Lock>,
+    opened_at: SystemTime,
+    start: Instant,
+}
+
+impl Opening {
+    /// Open the parcel, then record it in the history
+    pub fn finish(
+        self,
+        config: &ParcelConfig,
+        args: &OpenArgs,
+        opener: &dyn Opener,
+    ) -> anyhow::Result<()> {
+        let opened =
+            ParcelCommands::open_parcel(config, &self.name, &self.parcel, &args.run, opener);
+        self.record(opened.is_ok(), args);
+        opened?;
+        if config.settings.notify && !args.run.plan {
+            utils::notify(&t!("parcel-opened", name = self.name))?;
+        }
+
+        if args.wait {
+            // The parcel can be opened again while waiting
+            drop(self.lock);
+            engine::wait_until_closed(&self.name, &self.parcel)?;
+        }
+        Ok(())
+    }
+
+    fn record(&self, opened: bool, args: &OpenArgs) {
+        // Parcels read from stdin have no name to look them up by again
+        if args.run.plan || self.name == "-" {
+            return;
+        }
+        let recorded = History::open().and_then(|history| {
+            history.record(&self.name, self.opened_at, self.start.elapsed(), opened)
+        });
+        if let Err(e) = recorded {
+            eprintln!("{}", t!("history-failed", reason = format!("{:#}", e)));
         }
     }
 }
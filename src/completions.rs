@@ -0,0 +1,94 @@
+//! Shell completion scripts, and their installation where each shell looks for them.
+
+use std::{fs, io, path::PathBuf};
+
+use anyhow::Context as _;
+use clap::CommandFactory;
+use clap_complete::Shell;
+
+use crate::{cli::ParcelCLI, i18n::t, state::Store};
+
+/// The completion scripts written by [`install`], for `uninstall` to remove
+fn store() -> Store<Vec<PathBuf>> {
+    Store::state("completions")
+}
+
+/// The script registering completions with the shell.
+///
+/// It calls back into the binary, so completions follow the config.
+pub fn script(shell: Shell) -> anyhow::Result<Vec<u8>> {
+    let shells = clap_complete::env::Shells::builtins();
+    let completer = shells
+        .completer(&shell.to_string())
+        .ok_or_else(|| anyhow::anyhow!("unsupported shell: {}", shell))?;
+    let name = ParcelCLI::command().get_name().to_string();
+    let mut script = Vec::new();
+    completer.write_registration(
+        "COMPLETE",
+        &name,
+        &name,
+        &std::env::current_exe()?.to_string_lossy(),
+        &mut script,
+    )?;
+    Ok(script)
+}
+
+/// The shell of the user, from `$SHELL`
+pub fn detect() -> anyhow::Result<Shell> {
+    Shell::from_env().context(t!("completions-unknown-shell"))
+}
+
+/// Where the shell loads completion scripts from, along with the line to add to its rc file when it
+/// doesn't load them by itself
+fn location(shell: Shell) -> anyhow::Result<(PathBuf, Option<&'static str>)> {
+    let name = ParcelCLI::command().get_name().to_string();
+    let (path, rc_line) = match shell {
+        Shell::Bash => (
+            format!("~/.local/share/bash-completion/completions/{}", name),
+            None,
+        ),
+        Shell::Zsh => (
+            format!("~/.zsh/completions/_{}", name),
+            Some("fpath=(~/.zsh/completions $fpath); autoload -Uz compinit && compinit"),
+        ),
+        Shell::Fish => (format!("~/.config/fish/completions/{}.fish", name), None),
+        Shell::Elvish => (
+            format!("~/.config/elvish/lib/{}.elv", name),
+            Some("use kozutsumi"),
+        ),
+        Shell::PowerShell => (
+            format!("~/.config/powershell/{}.ps1", name),
+            Some(". ~/.config/powershell/kozutsumi.ps1"),
+        ),
+        _ => anyhow::bail!("unsupported shell: {}", shell),
+    };
+    Ok((
+        PathBuf::from(shellexpand::tilde(&path).into_owned()),
+        rc_line,
+    ))
+}
+
+/// Write the completion script where the shell loads it from, returning where it was written and the
+/// line to add to the shell's rc file, if any
+pub fn install(shell: Shell) -> anyhow::Result<(PathBuf, Option<&'static str>)> {
+    let (path, rc_line) = location(shell)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, script(shell)?)?;
+
+    let store = store();
+    let mut installed = store.load()?.unwrap_or_default();
+    if !installed.contains(&path) {
+        installed.push(path.clone());
+        store.save(&installed)?;
+    }
+    Ok((path, rc_line))
+}
+
+/// The completion scripts written by [`install`] that still exist
+pub fn installed() -> io::Result<Vec<PathBuf>> {
+    let mut installed = store().load()?.unwrap_or_default();
+    installed.retain(|path| path.exists());
+    Ok(installed)
+}
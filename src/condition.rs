@@ -0,0 +1,38 @@
+//! `when` expressions deciding whether a parcel opens, written in Rhai.
+
+use std::process::Command;
+
+use anyhow::Context as _;
+use rhai::Engine;
+
+use crate::{dotenv, i18n::t};
+
+/// Local hour, minute, and day of the week (1 for Monday), as told by `date`
+fn now() -> (i64, i64, i64) {
+    let output = Command::new("date")
+        .arg("+%H %M %u")
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+        .unwrap_or_default();
+    let mut fields = output
+        .split_whitespace()
+        .map(|field| field.parse().unwrap_or_default());
+    let mut next = || fields.next().unwrap_or_default();
+    (next(), next(), next())
+}
+
+/// Evaluate the expression, which can call `hour()`, `minute()`, `weekday()`, `is_weekday()`,
+/// and `env(name)`, e.g. `hour() < 18 && is_weekday()`
+pub fn eval(expr: &str) -> anyhow::Result<bool> {
+    let (hour, minute, weekday) = now();
+    let mut engine = Engine::new();
+    engine
+        .register_fn("hour", move || hour)
+        .register_fn("minute", move || minute)
+        .register_fn("weekday", move || weekday)
+        .register_fn("is_weekday", move || weekday <= 5)
+        .register_fn("env", |name: &str| dotenv::var(name).unwrap_or_default());
+    engine
+        .eval_expression::<bool>(expr)
+        .with_context(|| t!("when-invalid", expr = expr))
+}
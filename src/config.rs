@@ -1,32 +1,945 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fmt::Display,
-    io,
+    fs,
     path::{Path, PathBuf},
-    process::Output,
+    str::FromStr,
+    time::Duration,
 };
 
-use config::{Config, ConfigError, File, FileFormat};
+use anyhow::Context as _;
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
 use std::process::Command;
 use url::Url;
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::{
+    autostart,
+    calendar::CalendarSettings,
+    cli::{Chooser, ColorChoice},
+    dotenv,
+    error::ParcelError,
+    hook::HookAction,
+    i18n::t,
+    placeholders,
+};
+
+/// Formats the config can be converted to
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ConfigFormat {
+    Yaml,
+    #[cfg(feature = "json")]
+    Json,
+    #[cfg(feature = "toml")]
+    Toml,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct ParcelConfig {
+    /// Global options, kept under the reserved `settings` key
+    #[serde(default)]
+    pub settings: Settings,
+    /// Named entries that parcels can reference by name, kept under the reserved `entries` key
+    #[serde(default)]
+    pub entries: HashMap<String, Entry>,
+    /// Commands opening the entries that start with a prefix, kept under the reserved `handlers` key
+    #[serde(default)]
+    pub handlers: BTreeMap<String, String>,
     #[serde(flatten)]
-    pub parcels: HashMap<String, Vec<Entry>>,
+    pub parcels: HashMap<String, Parcel>,
+}
+
+/// Options that apply to every parcel, read from the `settings:` block of the config.
+///
+/// ```yml
+/// settings:
+///   chooser: fzf
+///   fzf_args: ["--height=40%"]
+///   concurrency: 4
+///   notify: true
+///   colors: never
+///   icons: nerd
+///   theme:
+///     parcel: cyan.bold
+///     error: red
+///   dotenv: ~/.config/kozutsumi/.env
+///   terminal: Ghostty
+///   editor: zed
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Choosers used by `choose` when `--chooser` isn't passed, the first available one being shown.
+    /// fzf needs to be installed, and every chooser but `gui` needs a terminal
+    #[serde(deserialize_with = "choosers")]
+    pub chooser: Vec<Chooser>,
+    /// Extra arguments appended to the fzf invocation
+    pub fzf_args: Vec<String>,
+    /// Maximum number of entries opened at the same time (defaults to one at a time)
+    pub concurrency: Option<usize>,
+    /// Show a system notification once a parcel has been opened
+    pub notify: bool,
+    /// When to use colors in output: `auto` (terminals only), `always`, or `never`
+    pub colors: ColorChoice,
+    /// Glyphs shown next to entries in `list` and the fzf preview
+    pub icons: Icons,
+    /// Colors used for output, when colors are enabled
+    pub theme: Theme,
+    /// Language of messages, e.g. `en`. Defaults to the one from `LANG`
+    pub locale: Option<String>,
+    /// Check for a newer release (at most once a day) and mention it after commands
+    pub update_check: bool,
+    /// `.env` file whose variables can be used as `${VAR}` in entries
+    pub dotenv: PathBuf,
+    /// Terminal application opening `termdir:` entries
+    pub terminal: String,
+    /// Editor opening `code:` entries
+    pub editor: Editor,
+    /// How loosely `open`, `list`, and the other commands match parcel names
+    pub matching: Matching,
+    /// What the shell hook does with the parcels of a project's `.kozutsumi.yml`: `suggest` or `open`
+    pub hook: HookAction,
+    /// Shortcut setting the Focus mode of `focus:` entries.
+    /// It receives the name of the mode as input, or `off` to turn Focus off
+    pub focus_shortcut: String,
+    /// Parcel opened by `open` when no name is given, unless `PARCEL_DEFAULT` is set
+    pub default_parcel: Option<String>,
+    /// What to do with the file entries whose path doesn't exist: `skip`, `warn`, or `error` (the default)
+    pub missing_path: MissingPath,
+    /// Parcels the daemon opens ahead of calendar events
+    pub calendar: CalendarSettings,
+}
+
+/// Styles of the different parts of the output.
+///
+/// Each style is a dot-separated list of colors (`red`, `blue`, a 256-color number like `208`)
+/// and attributes (`bold`, `dim`, `italic`, `underlined`), e.g. `cyan.bold`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub parcel: Style,
+    pub phase: Style,
+    pub app: Style,
+    pub file: Style,
+    pub url: Style,
+    pub shell: Style,
+    pub error: Style,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Style(pub String);
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            parcel: Style("cyan.bold".into()),
+            phase: Style("magenta".into()),
+            app: Style("green".into()),
+            file: Style("blue".into()),
+            url: Style("yellow".into()),
+            shell: Style("red".into()),
+            error: Style("red.bold".into()),
+        }
+    }
+}
+
+/// Style of the glyph shown next to each entry, based on its type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Icons {
+    #[default]
+    None,
+    /// ASCII markers such as `[u]` for URLs, which work in any terminal
+    Ascii,
+    /// Nerd Font icons, which need a patched font
+    Nerd,
+}
+
+/// How loosely parcel names are matched, from the strictest.
+///
+/// A looser level also tries the stricter ones first, and a name only matches if it matches a single parcel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Matching {
+    /// Only the full name
+    Exact,
+    /// The start of the name, e.g. `lang` for `language-learning`
+    #[default]
+    Prefix,
+    /// Any part of the name, e.g. `learn` for `language-learning`
+    Substring,
+    /// The initials of the words of the name (e.g. `ll` for `language-learning`),
+    /// or its letters in order (e.g. `wrk` for `work`)
+    Abbreviation,
+}
+
+/// What opening a parcel does with the file entries whose path doesn't exist
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingPath {
+    /// Leave the entry unopened without saying anything
+    Skip,
+    /// Leave the entry unopened with a warning
+    Warn,
+    /// Fail the entry, like an entry that couldn't be opened
+    #[default]
+    Error,
+}
+
+/// Editor opening folders and files of `code:` entries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Editor {
+    /// Visual Studio Code
+    #[default]
+    Code,
+    Zed,
+    /// Sublime Text
+    Sublime,
+}
+
+impl Editor {
+    /// Name of the editor's application
+    pub fn app(self) -> &'static str {
+        match self {
+            Self::Code => "Visual Studio Code",
+            Self::Zed => "Zed",
+            Self::Sublime => "Sublime Text",
+        }
+    }
+}
+
+/// A single chooser, or a list of them in order of preference
+fn choosers<'de, D>(deserializer: D) -> Result<Vec<Chooser>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        One(Chooser),
+        Many(Vec<Chooser>),
+    }
+
+    Ok(match Repr::deserialize(deserializer)? {
+        Repr::One(chooser) => vec![chooser],
+        Repr::Many(choosers) => choosers,
+    })
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            chooser: Vec::new(),
+            fzf_args: Vec::new(),
+            concurrency: None,
+            notify: false,
+            colors: ColorChoice::default(),
+            icons: Icons::default(),
+            theme: Theme::default(),
+            locale: None,
+            update_check: false,
+            dotenv: PathBuf::from("~/.config/kozutsumi/.env"),
+            terminal: "Terminal".into(),
+            editor: Editor::default(),
+            focus_shortcut: "Set Focus".into(),
+            hook: HookAction::default(),
+            matching: Matching::default(),
+            default_parcel: None,
+            missing_path: MissingPath::default(),
+            calendar: CalendarSettings::default(),
+        }
+    }
+}
+
+/// A group of entries opened together.
+///
+/// Written either as a plain list of entries, or as a map splitting the entries
+/// into named phases that are opened one after the other:
+///
+/// ```yml
+/// dev:
+///   icon: 🛠️
+///   timeout: 2m
+///   phases:
+///     setup:
+///       - sh:docker compose up -d
+///     apps:
+///       parallel: true
+///       entries: [Zed, Ghostty, http://localhost:3000]
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(remote = "Self")]
+pub struct Parcel {
+    /// Emoji, or path to an image, shown next to the parcel's name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Short description shown next to the parcel's name in the choosers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Tags shown next to the parcel's name in the choosers, which can be searched for in fzf
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Entries of a parcel that isn't split into phases
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "EntryList::flattened"
+    )]
+    pub entries: Vec<Step>,
+    /// Phases in the order they are opened in
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "phases")]
+    pub phases: Vec<Phase>,
+    /// Entries opened last, only if every other entry opened successfully
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "EntryList::flattened"
+    )]
+    pub then: Vec<Step>,
+    /// Time after which the entries still opening are cancelled, e.g. `60s`
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "duration")]
+    pub timeout: Option<Duration>,
+    /// Times a failed entry is opened again, unless the entry sets its own `retries`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+    /// Skip the entries that are already open, like `open --idempotent`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub idempotent: bool,
+    /// Cron expression of when the parcel is opened once `schedule apply` is run, e.g. `0 9 * * 1-5`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<String>,
+    /// Lua script returning more entries when the parcel is opened
+    #[cfg(feature = "lua")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lua: Option<String>,
+    /// Rhai expression that has to be true for the parcel to open, e.g. `hour() < 18`
+    #[cfg(feature = "rhai")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+}
+
+/// A named step of a [`Parcel`], written as a list of entries or as a map with options.
+#[derive(Debug, Clone, Default)]
+pub struct Phase {
+    pub name: String,
+    /// Open the entries of this phase at the same time instead of one by one
+    pub parallel: bool,
+    pub entries: Vec<Step>,
+}
+
+/// An entry of a parcel, along with the options controlling how it is opened.
+///
+/// Written as the entry alone, or as a map with the entry under `entry` and its options next to it:
+///
+/// ```yml
+/// - entry: sh:vpn up
+///   retries: 2
+/// ```
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub entry: Entry,
+    pub options: EntryOptions,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryOptions {
+    /// Times the entry is opened again when it fails, instead of the parcel's `retries`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+    /// Condition that has to be met once the entry is opened, before the next entries are
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wait_for: Option<WaitFor>,
+    /// Report the entry failing as a warning, without failing the parcel
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+    /// Skip every entry after this one if it fails
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub critical: bool,
+    /// Time after which opening the entry is abandoned and reported as failed, e.g. `10s`
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "duration")]
+    pub timeout: Option<Duration>,
+    /// What to do when the path of the file entry doesn't exist, instead of `settings.missing_path`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing_path: Option<MissingPath>,
+    /// Name of a group whose entries open one after the other, while other entries open alongside them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+}
+
+/// Readiness an entry is waited for, e.g. `{ process: Docker, timeout: 30s }` or `{ port: 5432 }`.
+/// When both a process and a port are given, both have to be ready
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WaitFor {
+    /// Name of a process that has to be running
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process: Option<String>,
+    /// Local port that has to accept connections
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// Time after which the entry is considered failed, 30 seconds by default
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "duration")]
+    pub timeout: Option<Duration>,
+}
+
+impl From<Entry> for Step {
+    fn from(entry: Entry) -> Self {
+        Self {
+            entry,
+            options: EntryOptions::default(),
+        }
+    }
+}
+
+impl Serialize for Step {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        #[derive(Serialize)]
+        struct Detailed<'a> {
+            entry: &'a Entry,
+            #[serde(flatten)]
+            options: &'a EntryOptions,
+        }
+
+        if self.options == EntryOptions::default() {
+            self.entry.serialize(serializer)
+        } else {
+            Detailed {
+                entry: &self.entry,
+                options: &self.options,
+            }
+            .serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Step {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Detailed {
+                entry: Entry,
+                #[serde(flatten)]
+                options: EntryOptions,
+            },
+            Plain(Entry),
+        }
+
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Detailed { entry, options } => Self { entry, options },
+            Repr::Plain(entry) => entry.into(),
+        })
+    }
+}
+
+impl Parcel {
+    /// Iterate over every entry of the parcel, across all of its phases and `then`
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.entries
+            .iter()
+            .chain(self.phases.iter().flat_map(|phase| &phase.entries))
+            .chain(&self.then)
+            .map(|step| &step.entry)
+    }
+
+    pub fn entries_mut(&mut self) -> impl Iterator<Item = &mut Entry> {
+        self.entries
+            .iter_mut()
+            .chain(self.phases.iter_mut().flat_map(|phase| &mut phase.entries))
+            .chain(&mut self.then)
+            .map(|step| &mut step.entry)
+    }
+
+    /// Keep only the entries for which `keep` is true, dropping the phases left empty
+    pub fn retain_entries(&mut self, keep: impl Fn(&Entry) -> bool) {
+        self.entries.retain(|step| keep(&step.entry));
+        for phase in &mut self.phases {
+            phase.entries.retain(|step| keep(&step.entry));
+        }
+        self.phases.retain(|phase| !phase.entries.is_empty());
+        self.then.retain(|step| keep(&step.entry));
+    }
+
+    /// The icon, unless it is a path to an image, which can't be shown in a terminal
+    pub fn text_icon(&self) -> Option<&str> {
+        self.icon
+            .as_deref()
+            .filter(|icon| !icon.starts_with(['/', '~']))
+    }
+
+    /// Whether the parcel can be written back as a plain list of entries
+    fn is_plain(&self) -> bool {
+        #[cfg(feature = "lua")]
+        if self.lua.is_some() {
+            return false;
+        }
+        #[cfg(feature = "rhai")]
+        if self.when.is_some() {
+            return false;
+        }
+        self.icon.is_none()
+            && self.description.is_none()
+            && self.tags.is_empty()
+            && self.phases.is_empty()
+            && self.then.is_empty()
+            && self.timeout.is_none()
+            && self.retries.is_none()
+            && !self.idempotent
+            && self.schedule.is_none()
+    }
+}
+
+/// A list of entries, in which nested lists are flattened.
+///
+/// This lets a block of entries shared through an anchor be spliced into another list with `- *block`.
+struct EntryList(Vec<Step>);
+
+impl EntryList {
+    fn flattened<'de, D>(deserializer: D) -> Result<Vec<Step>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Self::deserialize(deserializer).map(|list| list.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for EntryList {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Item {
+            Entry(Step),
+            List(Vec<Item>),
+        }
+
+        fn flatten(items: Vec<Item>, entries: &mut Vec<Step>) {
+            for item in items {
+                match item {
+                    Item::Entry(entry) => entries.push(entry),
+                    Item::List(items) => flatten(items, entries),
+                }
+            }
+        }
+
+        let mut entries = Vec::new();
+        flatten(Vec::<Item>::deserialize(deserializer)?, &mut entries);
+        Ok(Self(entries))
+    }
+}
+
+impl<'de> Deserialize<'de> for Parcel {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        // Only lives while the parcel is deserialized
+        #[allow(clippy::large_enum_variant)]
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Entries(EntryList),
+            #[serde(with = "Parcel")]
+            Detailed(Parcel),
+        }
+
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Entries(EntryList(entries)) => Self {
+                entries,
+                ..Default::default()
+            },
+            Repr::Detailed(parcel) => parcel,
+        })
+    }
+}
+
+impl Serialize for Parcel {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        if self.is_plain() {
+            self.entries.serialize(serializer)
+        } else {
+            Parcel::serialize(self, serializer)
+        }
+    }
+}
+
+/// (De)serialization of durations written as a number followed by a unit, e.g. `60s`
+pub mod duration {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    /// Parse a duration like `500ms`, `60s`, `5m`, or `1h`. A bare number is a number of seconds
+    pub fn parse(s: &str) -> Result<Duration, String> {
+        let s = s.trim();
+        let (value, unit) = s.split_at(
+            s.find(|c: char| !c.is_ascii_digit() && c != '.')
+                .unwrap_or(s.len()),
+        );
+        let value = value
+            .parse::<f64>()
+            .map_err(|_| format!("invalid duration `{}`", s))?;
+        let seconds = match unit.trim() {
+            "ms" => value / 1000.0,
+            "" | "s" => value,
+            "m" => value * 60.0,
+            "h" => value * 60.0 * 60.0,
+            unit => {
+                return Err(format!(
+                    "unknown unit `{}` in duration `{}`, expected ms, s, m, or h",
+                    unit, s
+                ));
+            }
+        };
+        Duration::try_from_secs_f64(seconds).map_err(|_| format!("invalid duration `{}`", s))
+    }
+
+    /// Format a duration the way [`parse`] reads it
+    pub fn format(duration: Duration) -> String {
+        let secs = duration.as_secs();
+        match duration.subsec_nanos() {
+            0 if secs > 0 && secs.is_multiple_of(3600) => format!("{}h", secs / 3600),
+            0 if secs > 0 && secs.is_multiple_of(60) => format!("{}m", secs / 60),
+            0 => format!("{}s", secs),
+            _ => format!("{}ms", duration.as_millis()),
+        }
+    }
+
+    pub fn serialize<S>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match duration {
+            Some(duration) => serializer.serialize_str(&format(*duration)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Seconds(u64),
+            Text(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Seconds(secs) => Ok(Some(Duration::from_secs(secs))),
+            Repr::Text(text) => parse(&text).map(Some).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// (De)serialization of phases as an ordered map of phase names to their entries
+mod phases {
+    use std::fmt;
+
+    use serde::{
+        Deserialize, Deserializer, Serialize, Serializer,
+        de::{MapAccess, Visitor},
+        ser::SerializeMap,
+    };
+
+    use super::{EntryList, Phase};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum PhaseRepr<E> {
+        Entries(E),
+        Detailed {
+            #[serde(default)]
+            parallel: bool,
+            entries: E,
+        },
+    }
+
+    pub fn serialize<S>(phases: &[Phase], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(phases.len()))?;
+        for phase in phases {
+            let entries = &phase.entries;
+            let value = if phase.parallel {
+                PhaseRepr::Detailed {
+                    parallel: true,
+                    entries,
+                }
+            } else {
+                PhaseRepr::Entries(entries)
+            };
+            map.serialize_entry(&phase.name, &value)?;
+        }
+        map.end()
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Phase>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct PhasesVisitor;
+
+        impl<'de> Visitor<'de> for PhasesVisitor {
+            type Value = Vec<Phase>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a map of phase names to entries")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut phases = Vec::new();
+                while let Some((name, repr)) = map.next_entry::<String, PhaseRepr<EntryList>>()? {
+                    let (parallel, EntryList(entries)) = match repr {
+                        PhaseRepr::Entries(entries) => (false, entries),
+                        PhaseRepr::Detailed { parallel, entries } => (parallel, entries),
+                    };
+                    phases.push(Phase {
+                        name,
+                        parallel,
+                        entries,
+                    });
+                }
+                Ok(phases)
+            }
+        }
+
+        deserializer.deserialize_map(PhasesVisitor)
+    }
+}
+
+/// Entries tagged with their type when serialized, e.g. `{"type": "url", "value": "https://example.com"}`,
+/// so that consumers of the JSON outputs don't have to tell the type of an entry from its prefix
+#[cfg(feature = "json")]
+pub mod tagged {
+    use std::cell::Cell;
+
+    thread_local! {
+        static TAGGED: Cell<bool> = const { Cell::new(false) };
+    }
+
+    /// Run `f` with the entries it serializes tagged, including those nested in parcels and phases
+    pub fn scope<T>(f: impl FnOnce() -> T) -> T {
+        let previous = TAGGED.replace(true);
+        let result = f();
+        TAGGED.set(previous);
+        result
+    }
+
+    pub(super) fn enabled() -> bool {
+        TAGGED.get()
+    }
 }
 
 impl ParcelConfig {
-    pub fn load(config_path: impl AsRef<Path>) -> Result<Self, ConfigError> {
-        let conf = Config::builder()
-            .add_source(
-                File::with_name(&config_path.as_ref().to_string_lossy()).format(FileFormat::Yaml),
-            )
-            .build()?
-            .try_deserialize()?;
+    pub fn load(config_path: impl AsRef<Path>) -> Result<Self, ParcelError> {
+        let path = config_path.as_ref();
+        let mut mapping = Self::load_file(path, &mut Vec::new())?;
+        let local = Self::local_path(path);
+        if local.exists() {
+            Self::merge_document(&mut mapping, Self::load_file(&local, &mut Vec::new())?);
+        }
+        Self::from_mapping(mapping).map_err(|e| ParcelError::invalid_yaml(path, e))
+    }
+
+    /// The machine-specific config merged over the config at the path when it exists,
+    /// `parcel.local.yml` for `parcel.yml`, meant to be left out of the synced dotfiles
+    pub fn local_path(config_path: &Path) -> PathBuf {
+        let stem = config_path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy();
+        let name = match config_path.extension() {
+            Some(ext) => format!("{}.local.{}", stem, ext.to_string_lossy()),
+            None => format!("{}.local", stem),
+        };
+        config_path.with_file_name(name)
+    }
+
+    /// The config at the path along with the files it includes, as written rather than resolved.
+    ///
+    /// Keys keep the order they are written in, anchors and includes are resolved,
+    /// and the keys holding anchors are dropped. The config has to be valid.
+    pub fn load_mapping(config_path: impl AsRef<Path>) -> anyhow::Result<Mapping> {
+        let path = config_path.as_ref();
+        let mut mapping = Self::load_file(path, &mut Vec::new())?;
+        mapping.retain(|key, _| !key.as_str().is_some_and(|key| key.starts_with('.')));
+        Self::from_mapping(mapping.clone()).map_err(|e| ParcelError::invalid_yaml(path, e))?;
+        Ok(mapping)
+    }
 
-        Ok(conf)
+    /// The config at the path, along with the files it includes, written in another format.
+    ///
+    /// Parcels and entries keep the order they are written in, see [`ParcelConfig::load_mapping`].
+    pub fn convert(config_path: impl AsRef<Path>, format: ConfigFormat) -> anyhow::Result<String> {
+        let mapping = Self::load_mapping(config_path)?;
+        Ok(match format {
+            ConfigFormat::Yaml => serde_yaml::to_string(&mapping)?,
+            #[cfg(feature = "json")]
+            ConfigFormat::Json => serde_json::to_string_pretty(&mapping)? + "\n",
+            #[cfg(feature = "toml")]
+            ConfigFormat::Toml => toml::to_string(&mapping)?,
+        })
+    }
+
+    /// Load a config file along with the files it includes, merged into a single mapping.
+    ///
+    /// Files listed under `include:` are relative to the including file, and are merged
+    /// in order before the including file's own contents, which take precedence.
+    /// `chain` holds the files currently being loaded, to detect include cycles.
+    fn load_file(path: &Path, chain: &mut Vec<PathBuf>) -> Result<Mapping, ParcelError> {
+        let unreadable = |source| ParcelError::ConfigNotFound {
+            path: path.to_path_buf(),
+            source,
+        };
+        let canonical = fs::canonicalize(path).map_err(unreadable)?;
+        if chain.contains(&canonical) {
+            return Err(ParcelError::invalid(
+                path,
+                t!("config-include-cycle", path = path.display()),
+            ));
+        }
+
+        let contents = fs::read_to_string(&canonical).map_err(unreadable)?;
+        #[cfg(feature = "toml")]
+        let parsed = if canonical.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&contents).map_err(|e| ParcelError::invalid(path, e))
+        } else {
+            Self::parse_documents(&contents).map_err(|e| ParcelError::invalid_yaml(path, e))
+        };
+        #[cfg(not(feature = "toml"))]
+        let parsed =
+            Self::parse_documents(&contents).map_err(|e| ParcelError::invalid_yaml(path, e));
+        let mut own = parsed?;
+        let includes = match own.remove("include") {
+            None => Vec::new(),
+            Some(Value::String(include)) => vec![include],
+            Some(includes) => serde_yaml::from_value::<Vec<String>>(includes)
+                .map_err(|e| ParcelError::invalid_yaml(path, e))?,
+        };
+
+        let dir = canonical
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        chain.push(canonical);
+        let mut merged = Mapping::new();
+        for include in includes {
+            let include = dir.join(shellexpand::tilde(&include).as_ref());
+            Self::merge_document(&mut merged, Self::load_file(&include, chain)?);
+        }
+        chain.pop();
+
+        Self::merge_document(&mut merged, own);
+        Ok(merged)
+    }
+
+    /// Parse the documents of a config file, resolving YAML anchors, aliases, and `<<` merge keys.
+    ///
+    /// The config can be split into several documents separated by `---`, which are merged in order:
+    /// the `settings`, `entries`, and `handlers` blocks are merged key by key, and a parcel defined in several
+    /// documents is replaced by its last definition. In both cases later documents win.
+    fn parse_documents(contents: &str) -> Result<Mapping, serde_yaml::Error> {
+        let mut merged = Mapping::new();
+        for document in serde_yaml::Deserializer::from_str(contents) {
+            let mut value = Value::deserialize(document)?;
+            value.apply_merge()?;
+            match value {
+                // An empty document is an empty config
+                Value::Null => {}
+                Value::Mapping(mapping) => Self::merge_document(&mut merged, mapping),
+                _ => {
+                    return Err(serde::de::Error::custom(
+                        "each document of the config must be a mapping",
+                    ));
+                }
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Build the config from its merged documents.
+    ///
+    /// Top-level keys starting with `.` are dropped, so they can hold anchors without becoming parcels.
+    /// The `.env` file of the settings is loaded first, since entries expand its variables.
+    fn from_mapping(mut mapping: Mapping) -> Result<Self, serde_yaml::Error> {
+        mapping.retain(|key, _| !key.as_str().is_some_and(|key| key.starts_with('.')));
+        let settings = match mapping.get("settings") {
+            Some(settings) => serde_yaml::from_value(settings.clone())?,
+            None => Settings::default(),
+        };
+        dotenv::load(&settings.dotenv);
+        Ok(serde_yaml::from_value::<Self>(Value::Mapping(mapping))?.resolve_entries())
+    }
+
+    /// Merge a document of the config into the previous ones, see [`ParcelConfig::parse_documents`]
+    fn merge_document(merged: &mut Mapping, document: Mapping) {
+        for (key, value) in document {
+            let is_block = matches!(key.as_str(), Some("settings" | "entries" | "handlers"));
+            if is_block
+                && let (Some(Value::Mapping(previous)), Value::Mapping(block)) =
+                    (merged.get_mut(&key), &value)
+            {
+                previous.extend(block.clone());
+                continue;
+            }
+            merged.insert(key, value);
+        }
+    }
+
+    /// Resolve the entries of every parcel, see [`ParcelConfig::resolve_entry`]
+    fn resolve_entries(mut self) -> Self {
+        let mut parcels = std::mem::take(&mut self.parcels);
+        for entry in parcels.values_mut().flat_map(Parcel::entries_mut) {
+            self.resolve_entry(entry);
+        }
+        self.parcels = parcels;
+        self
+    }
+
+    /// Replace the entry with the one it is an alias for, if any,
+    /// then fill in the settings it doesn't override.
+    ///
+    /// Bare names parse as [`Entry::App`], so that is the only variant that can refer to an alias.
+    pub fn resolve_entry(&self, entry: &mut Entry) {
+        if let Entry::App(app) = entry
+            && app.env.is_empty()
+            && let Some(alias) = self.entries.get(&app.name)
+        {
+            *entry = alias.clone();
+        }
+        match entry {
+            Entry::TermDir { terminal, .. } => {
+                terminal.get_or_insert_with(|| self.settings.terminal.clone());
+            }
+            Entry::Code { editor, .. } => {
+                editor.get_or_insert(self.settings.editor);
+            }
+            Entry::Focus(focus) => {
+                focus
+                    .shortcut
+                    .get_or_insert_with(|| self.settings.focus_shortcut.clone());
+            }
+            _ => {}
+        }
     }
 }
 
@@ -38,19 +951,38 @@ impl ParcelConfig {
 /// - URLs can be prefixed with `http:`, `https:`
 //    , or no prefix at all (example.com)
 /// - Shell commands are prefixed with `sh:`
-#[derive(Debug, Serialize)]
-#[serde(untagged)]
+/// - Directories to open in a new terminal window are prefixed with `termdir:`
+/// - Folders and files to open in an editor are prefixed with `code:`
+/// - Docker Compose projects to bring up are prefixed with `compose:`
+/// - launchd services to start are prefixed with `launchd:`
+/// - Playlists to play are prefixed with `play:`
+/// - Focus modes to turn on are prefixed with `focus:`
+/// - Keyboard Maestro macros and BetterTouchTool named triggers are prefixed with `km:` and `btt:`
+/// - `meeting:next` joins the next meeting of the calendar
+///
+/// Applications can also be written as a map, to launch them with environment variables:
+///
+/// ```yml
+/// - app: Ghostty
+///   env:
+///     RUST_LOG: debug
+/// ```
+///
+/// `$VAR` and `${VAR}` are expanded from the `.env` file of the settings, then from the environment.
+/// Shell commands are left as they are, and run with the variables of the `.env` file in their environment.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Entry {
     /// An application name to be opened.
     ///
     /// - On macOS, you can just specify the name of the application (e.g., Safari)
-    App(String),
+    App(App),
     /// A file path to be opened.
-    /// Must be prefixed with `~` or `/`.
+    /// Must be prefixed with `~` or `/`, or be a `file://` URL.
     ///
     /// # Examples
     /// - `/Users/username/Documents` (absolute path)
     /// - `~/Documents` (home directory)
+    /// - `file:///Users/username/My%20Report.pdf` (percent-decoded)
     ///
     /// If the path is a directory, it will be opened in the default file manager.
     /// If the path is a file, it will be opened with the default application for that file type.
@@ -67,23 +999,597 @@ pub enum Entry {
     ///
     /// The command will be executed using the `sh` shell.
     ///
+    /// Written as a map to run a long-lived command in the background, such as a dev server.
+    /// Its process is tracked so that `stop` can terminate it:
+    ///
+    /// ```yml
+    /// - sh: npm run dev
+    ///   detach: true
+    /// ```
+    ///
     /// **USE WITH CAUTION, AS THIS CAN EXECUTE ANY COMMAND ON YOUR SYSTEM.**
     #[cfg(feature = "shell")]
-    Shell(String),
+    Shell { command: String, detach: bool },
+    /// Shell commands run one after the other, the output of each fed as input to the next.
+    /// Written as a string prefixed with `pipe:`, the commands separated by ` | `,
+    /// or as a map when a command holds a ` | ` of its own:
+    ///
+    /// ```yml
+    /// - pipeline:
+    ///     - curl -s https://tickets.example.com/api/ACME-42
+    ///     - jq -r '.title + " " + .url'
+    ///     - pbcopy
+    /// ```
+    ///
+    /// **USE WITH CAUTION, AS THIS CAN EXECUTE ANY COMMAND ON YOUR SYSTEM.**
+    #[cfg(feature = "shell")]
+    Pipeline { pipeline: Vec<String> },
+    /// A directory opened in a new terminal window.
+    /// Must be prefixed with `termdir:`.
+    ///
+    /// The terminal defaults to `settings.terminal`, and can be picked per entry:
+    ///
+    /// ```yml
+    /// - termdir: ~/Projects/acme
+    ///   terminal: iTerm
+    /// ```
+    TermDir {
+        dir: PathBuf,
+        terminal: Option<String>,
+    },
+    /// A folder or file opened in an editor, rather than in Finder or its default application.
+    /// Must be prefixed with `code:`.
+    ///
+    /// The editor defaults to `settings.editor`, and can be picked per entry:
+    ///
+    /// ```yml
+    /// - code: ~/Projects/acme
+    ///   editor: zed
+    /// ```
+    Code {
+        path: PathBuf,
+        editor: Option<Editor>,
+    },
+    /// A Docker Compose project brought up with `docker compose up`, and down again by `close`.
+    /// Must be prefixed with `compose:`, followed by the project's directory or compose file.
+    ///
+    /// Written as a map to pick a profile, or to stay attached:
+    ///
+    /// ```yml
+    /// - compose: ~/Projects/acme
+    ///   profile: web
+    ///   detach: false
+    /// ```
+    Compose(Compose),
+    /// A launchd service of the current user, started with `launchctl kickstart`.
+    /// Must be prefixed with `launchd:`, followed by the label of the service.
+    ///
+    /// Closing the entry boots the service out, which unloads it until it is bootstrapped again.
+    Launchd(String),
+    /// A playlist played in Music or Spotify, and paused by `close`.
+    /// Must be prefixed with `play:`, followed by the name of a Music playlist.
+    ///
+    /// Written as a map to play a Spotify playlist, using its URI:
+    ///
+    /// ```yml
+    /// - play: spotify:playlist:37i9dQZF1DWZeKCadgRdKQ
+    ///   app: spotify
+    /// ```
+    Play(Play),
+    /// A Focus mode turned on through a shortcut of the Shortcuts app, see `settings.focus_shortcut`.
+    /// Must be prefixed with `focus:`, followed by the name of the mode.
+    ///
+    /// Written as a map to turn Focus off again on `close`:
+    ///
+    /// ```yml
+    /// - focus: Work
+    ///   restore: true
+    /// ```
+    Focus(Focus),
+    /// A Keyboard Maestro macro, fired through the Keyboard Maestro Engine.
+    /// Must be prefixed with `km:`, followed by the name or UID of the macro.
+    KeyboardMaestro(String),
+    /// A BetterTouchTool named trigger, fired through its URL scheme.
+    /// Must be prefixed with `btt:`, followed by the name of the trigger.
+    BetterTouchTool(String),
+    /// The video call link of the next meeting in Calendar, written `meeting:next`.
+    ///
+    /// Events are read with [icalBuddy](https://hasseg.org/icalBuddy/), and the first Zoom, Meet,
+    /// Teams, or Webex link found in their URL, location, or notes is opened.
+    NextMeeting,
+    /// The most recently modified files of a directory, found when the parcel is opened.
+    /// Must be prefixed with `recent:`, followed by the directory and optionally how many files to open,
+    /// e.g. `recent:~/Downloads?limit=3`. Hidden files are ignored
+    Recent { dir: PathBuf, limit: usize },
+    /// The documents found by a Spotlight query, which can be quoted.
+    /// Must be prefixed with `mdfind:`, e.g. `mdfind:"kMDItemDisplayName == 'Q3 Roadmap*'"`.
+    ///
+    /// When several documents match, the one to open is asked for when the parcel is opened
+    Spotlight(String),
+    /// A file previewed with Quick Look instead of being opened in its application.
+    /// Must be prefixed with `ql:`, followed by the path of the file.
+    ///
+    /// The preview stays open in the background, and is closed by `stop`
+    QuickLook(PathBuf),
 }
 
-impl Entry {
+/// Finds the first video call link of the upcoming events, and opens it
+const NEXT_MEETING_SCRIPT: &str = r#"url=$(icalBuddy -n -nc -ea -iep url,location,notes eventsToday+1 \
+    | grep -oE 'https://[^[:space:]<>"]*(zoom\.us|meet\.google\.com|teams\.microsoft\.com|webex\.com)[^[:space:]<>"]*' \
+    | head -n 1)
+[ -n "$url" ] && open "$url""#;
+
+/// A Focus mode to turn on, see [`Entry::Focus`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Focus {
+    #[serde(rename = "focus")]
+    pub mode: String,
+    /// Turn Focus off when the parcel is closed
+    #[serde(default)]
+    pub restore: bool,
+    /// Shortcut setting the mode, defaults to `settings.focus_shortcut`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shortcut: Option<String>,
+}
+
+impl Focus {
+    #[cfg(target_os = "macos")]
+    /// Run the shortcut with the given input
+    fn run_shortcut(&self, input: &str) -> Command {
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(r#"printf %s "$1" | shortcuts run "$0""#)
+            .arg(self.shortcut.as_deref().unwrap_or("Set Focus"))
+            .arg(input);
+        command
+    }
+}
+
+/// A playlist to play, see [`Entry::Play`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Play {
+    /// Name of the playlist in Music, or its URI in Spotify
+    #[serde(rename = "play")]
+    pub playlist: String,
+    #[serde(default)]
+    pub app: Player,
+}
+
+/// Application playing the playlists of `play:` entries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Player {
+    #[default]
+    Music,
+    Spotify,
+}
+
+impl Play {
     #[cfg(target_os = "macos")]
-    /// Open the entry using the appropriate method based on its type.
-    pub fn open(&self) -> io::Result<Output> {
-        let output = match self {
-            Self::App(app) => Command::new("open").arg("-a").arg(app).output()?,
-            Self::File(path_buf) => Command::new("open").arg(path_buf).output()?,
-            Self::Url(url) => Command::new("open").arg(url.as_str()).output()?,
-            #[cfg(feature = "shell")]
-            Self::Shell(cmd) => Command::new("sh").arg("-c").arg(cmd).output()?,
+    /// Run an AppleScript command sent to the player
+    fn tell(&self, action: &str) -> Command {
+        let app = match self.app {
+            Player::Music => "Music",
+            Player::Spotify => "Spotify",
         };
-        Ok(output)
+        let mut command = Command::new("osascript");
+        command
+            .arg("-e")
+            .arg(format!("tell application {:?} to {}", app, action));
+        command
+    }
+}
+
+/// A Docker Compose project, see [`Entry::Compose`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Compose {
+    /// Directory of the project, or its compose file
+    #[serde(rename = "compose", deserialize_with = "Compose::expanded")]
+    pub path: PathBuf,
+    /// Profile to enable, on top of the services without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Run the containers in the background
+    #[serde(default = "Compose::default_detach")]
+    pub detach: bool,
+}
+
+impl Compose {
+    fn expanded<'de, D>(deserializer: D) -> Result<PathBuf, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(|path| expand_path(&path))
+    }
+
+    fn default_detach() -> bool {
+        true
+    }
+
+    #[cfg(target_os = "macos")]
+    /// `docker compose`, run from the project's directory with its compose file and profile
+    fn command(&self) -> Command {
+        let mut command = Command::new("docker");
+        command.arg("compose");
+        if self.path.is_file() {
+            command.arg("-f").arg(&self.path);
+            if let Some(dir) = self.path.parent() {
+                command.current_dir(dir);
+            }
+        } else {
+            command.current_dir(&self.path);
+        }
+        if let Some(profile) = &self.profile {
+            command.arg("--profile").arg(profile);
+        }
+        command
+    }
+}
+
+/// An application, along with the environment variables it is launched with
+#[derive(Debug, Clone, PartialEq)]
+pub struct App {
+    pub name: String,
+    pub env: BTreeMap<String, String>,
+}
+
+impl From<String> for App {
+    fn from(name: String) -> Self {
+        Self {
+            name,
+            env: BTreeMap::new(),
+        }
+    }
+}
+
+impl Serialize for App {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        if self.env.is_empty() {
+            return serializer.serialize_str(&self.name);
+        }
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("app", &self.name)?;
+        map.serialize_entry("env", &self.env)?;
+        map.end()
+    }
+}
+
+/// Entries are written back the way they are parsed: as their prefixed string,
+/// or as a map when they have options that the string can't hold
+impl Serialize for Entry {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        #[cfg(feature = "json")]
+        if tagged::enabled() {
+            use serde::ser::SerializeMap;
+
+            struct Value<'a>(&'a Entry);
+            impl Serialize for Value<'_> {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: serde::Serializer,
+                {
+                    self.0.serialize_value(serializer)
+                }
+            }
+
+            let mut map = serializer.serialize_map(Some(2))?;
+            map.serialize_entry("type", self.kind())?;
+            map.serialize_entry("value", &Value(self))?;
+            return map.end();
+        }
+        self.serialize_value(serializer)
+    }
+}
+
+impl Entry {
+    /// Serialize the entry as written in the config
+    fn serialize_value<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        match self {
+            Self::App(app) => app.serialize(serializer),
+            #[cfg(feature = "shell")]
+            Self::Shell { command, detach } if *detach => {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("sh", command)?;
+                map.serialize_entry("detach", detach)?;
+                map.end()
+            }
+            #[cfg(feature = "shell")]
+            Self::Pipeline { pipeline } => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("pipeline", pipeline)?;
+                map.end()
+            }
+            Self::TermDir {
+                dir,
+                terminal: Some(terminal),
+            } => {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("termdir", dir)?;
+                map.serialize_entry("terminal", terminal)?;
+                map.end()
+            }
+            Self::Code {
+                path,
+                editor: Some(editor),
+            } => {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("code", path)?;
+                map.serialize_entry("editor", editor)?;
+                map.end()
+            }
+            Self::Compose(compose) if compose.profile.is_some() || !compose.detach => {
+                compose.serialize(serializer)
+            }
+            Self::Play(play) if play.app != Player::Music => play.serialize(serializer),
+            Self::Focus(focus) if focus.restore || focus.shortcut.is_some() => {
+                focus.serialize(serializer)
+            }
+            // The other entries display as their prefixed string
+            _ => serializer.collect_str(self),
+        }
+    }
+}
+
+impl Entry {
+    /// Whether the entry keeps running in the background once opened, instead of being waited for
+    pub fn detached(&self) -> bool {
+        match self {
+            #[cfg(feature = "shell")]
+            Self::Shell { detach, .. } => *detach,
+            Self::QuickLook(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Edit each text the entry is made of in place: its name, command, path, or URL,
+    /// and the values of its options. The type of the entry and its other options are kept
+    pub fn edit_texts(
+        &mut self,
+        mut edit: impl FnMut(&mut String) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        fn edit_path(
+            path: &mut PathBuf,
+            edit: &mut dyn FnMut(&mut String) -> anyhow::Result<()>,
+        ) -> anyhow::Result<()> {
+            let mut text = path.to_string_lossy().into_owned();
+            edit(&mut text)?;
+            *path = PathBuf::from(text);
+            Ok(())
+        }
+
+        match self {
+            Self::App(app) => {
+                edit(&mut app.name)?;
+                app.env.values_mut().try_for_each(edit)
+            }
+            Self::File(path)
+            | Self::QuickLook(path)
+            | Self::TermDir { dir: path, .. }
+            | Self::Code { path, .. }
+            | Self::Recent { dir: path, .. } => edit_path(path, &mut edit),
+            Self::Url(url) => {
+                let mut text = url.to_string();
+                edit(&mut text)?;
+                *url = Url::parse(&text)
+                    .with_context(|| format!("invalid URL `{}`", placeholders::redact(&text)))?;
+                Ok(())
+            }
+            #[cfg(feature = "shell")]
+            Self::Shell { command, .. } => edit(command),
+            #[cfg(feature = "shell")]
+            Self::Pipeline { pipeline } => pipeline.iter_mut().try_for_each(edit),
+            Self::Compose(compose) => {
+                edit_path(&mut compose.path, &mut edit)?;
+                compose.profile.iter_mut().try_for_each(edit)
+            }
+            Self::Play(play) => edit(&mut play.playlist),
+            Self::Focus(focus) => {
+                edit(&mut focus.mode)?;
+                focus.shortcut.iter_mut().try_for_each(edit)
+            }
+            Self::Launchd(text)
+            | Self::KeyboardMaestro(text)
+            | Self::BetterTouchTool(text)
+            | Self::Spotlight(text) => edit(text),
+            Self::NextMeeting => Ok(()),
+        }
+    }
+
+    /// Every type of entry, as returned by [`Entry::kind`]
+    pub const KINDS: &[&str] = &[
+        "app",
+        "file",
+        "url",
+        #[cfg(feature = "shell")]
+        "shell",
+        #[cfg(feature = "shell")]
+        "pipeline",
+        "termdir",
+        "code",
+        "compose",
+        "launchd",
+        "play",
+        "focus",
+        "km",
+        "btt",
+        "meeting",
+        "recent",
+        "mdfind",
+        "ql",
+    ];
+
+    /// Short name of the entry's type
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::App(_) => "app",
+            Self::File(_) => "file",
+            Self::Url(_) => "url",
+            #[cfg(feature = "shell")]
+            Self::Shell { .. } => "shell",
+            #[cfg(feature = "shell")]
+            Self::Pipeline { .. } => "pipeline",
+            Self::TermDir { .. } => "termdir",
+            Self::Code { .. } => "code",
+            Self::Compose(_) => "compose",
+            Self::Launchd(_) => "launchd",
+            Self::Play(_) => "play",
+            Self::Focus(_) => "focus",
+            Self::KeyboardMaestro(_) => "km",
+            Self::BetterTouchTool(_) => "btt",
+            Self::NextMeeting => "meeting",
+            Self::Recent { .. } => "recent",
+            Self::Spotlight(_) => "mdfind",
+            Self::QuickLook(_) => "ql",
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    /// The command that opens the entry
+    pub fn command(&self) -> Command {
+        let mut command;
+        match self {
+            Self::App(app) => {
+                command = Command::new("open");
+                command.arg("-a").arg(&app.name);
+                // Only applies when the app isn't already running
+                for (key, value) in &app.env {
+                    command.arg("--env").arg(format!("{}={}", key, value));
+                }
+            }
+            Self::File(path_buf) => {
+                command = Command::new("open");
+                command.arg(path_buf);
+            }
+            Self::Url(url) => {
+                command = Command::new("open");
+                command.arg(url.as_str());
+            }
+            #[cfg(feature = "shell")]
+            Self::Shell { command: cmd, .. } => {
+                command = Command::new("sh");
+                command.arg("-c").arg(cmd).envs(dotenv::vars());
+            }
+            // Only for showing and planning, opening pipes the commands to each other itself
+            #[cfg(feature = "shell")]
+            Self::Pipeline { pipeline } => {
+                command = Command::new("sh");
+                command
+                    .arg("-c")
+                    .arg(pipeline.join(" | "))
+                    .envs(dotenv::vars());
+            }
+            // Terminals open a new window in the directory they are given
+            Self::TermDir { dir, terminal } => {
+                command = Command::new("open");
+                command
+                    .arg("-a")
+                    .arg(terminal.as_deref().unwrap_or("Terminal"))
+                    .arg(dir);
+            }
+            Self::Code { path, editor } => {
+                command = Command::new("open");
+                command
+                    .arg("-a")
+                    .arg(editor.unwrap_or_default().app())
+                    .arg(path);
+            }
+            Self::Compose(compose) => {
+                command = compose.command();
+                command.arg("up");
+                if compose.detach {
+                    command.arg("-d");
+                }
+            }
+            Self::Launchd(label) => {
+                command = Command::new("launchctl");
+                command
+                    .arg("kickstart")
+                    .arg(format!("{}/{}", autostart::gui_domain(), label));
+            }
+            Self::Play(play) => {
+                command = play.tell(&match play.app {
+                    Player::Music => format!("play playlist {:?}", play.playlist),
+                    // Spotify plays any URI as a track, including playlists and albums
+                    Player::Spotify => format!("play track {:?}", play.playlist),
+                });
+            }
+            Self::Focus(focus) => command = focus.run_shortcut(&focus.mode),
+            Self::KeyboardMaestro(macro_name) => {
+                command = Command::new("osascript");
+                command.arg("-e").arg(format!(
+                    "tell application \"Keyboard Maestro Engine\" to do script {:?}",
+                    macro_name
+                ));
+            }
+            Self::BetterTouchTool(trigger) => {
+                let url = Url::parse_with_params(
+                    "btt://trigger_named/",
+                    [("trigger_name", trigger.as_str())],
+                )
+                .expect("the BetterTouchTool URL is valid");
+                command = Command::new("open");
+                command.arg(url.as_str());
+            }
+            Self::NextMeeting => {
+                command = Command::new("sh");
+                command.arg("-c").arg(NEXT_MEETING_SCRIPT);
+            }
+            Self::Recent { dir, limit } => {
+                let files = recent_files(dir, *limit);
+                // `open` fails without arguments, and an empty directory has nothing to open
+                command = Command::new(if files.is_empty() { "true" } else { "open" });
+                command.args(files);
+            }
+            // Queries are resolved to a single file before opening, this only runs for entries that weren't
+            Self::Spotlight(query) => {
+                command = Command::new("sh");
+                command
+                    .arg("-c")
+                    .arg(r#"mdfind "$0" | head -n 1 | while read -r f; do open "$f"; done"#)
+                    .arg(query);
+            }
+            Self::QuickLook(path) => {
+                command = Command::new("qlmanage");
+                command.arg("-p").arg(path);
+            }
+        }
+        command
+    }
+
+    #[cfg(target_os = "macos")]
+    /// The command undoing what opening the entry did, for the entries that can be closed
+    pub fn close_command(&self) -> Option<Command> {
+        match self {
+            Self::Compose(compose) => {
+                let mut command = compose.command();
+                command.arg("down");
+                Some(command)
+            }
+            Self::Launchd(label) => {
+                let mut command = Command::new("launchctl");
+                command
+                    .arg("bootout")
+                    .arg(format!("{}/{}", autostart::gui_domain(), label));
+                Some(command)
+            }
+            Self::Play(play) => Some(play.tell("pause")),
+            Self::Focus(focus) if focus.restore => Some(focus.run_shortcut("off")),
+            _ => None,
+        }
     }
 }
 
@@ -92,38 +1598,476 @@ impl<'de> Deserialize<'de> for Entry {
     where
         D: serde::Deserializer<'de>,
     {
-        match String::deserialize(deserializer)? {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Plain(String),
+            App {
+                app: String,
+                #[serde(default)]
+                env: BTreeMap<String, String>,
+            },
+            TermDir {
+                termdir: String,
+                terminal: Option<String>,
+            },
+            Code {
+                code: String,
+                editor: Option<Editor>,
+            },
+            Compose(Compose),
+            Play(Play),
+            Focus(Focus),
             #[cfg(feature = "shell")]
-            s if s.starts_with("sh:") => Ok(Self::Shell(s[3..].to_string())),
-            s if s.starts_with(['/', '~']) => {
-                Ok(Self::File(shellexpand::tilde(&s).into_owned().into()))
-            }
-            s if let Ok(url) = Url::parse(&s) => Ok(Self::Url(url)),
-            s => Ok(Self::App(s)),
+            Shell {
+                sh: String,
+                #[serde(default)]
+                detach: bool,
+            },
+            #[cfg(feature = "shell")]
+            Pipeline {
+                pipeline: Vec<String>,
+            },
         }
+
+        let s = match Repr::deserialize(deserializer)? {
+            Repr::Plain(s) => s,
+            Repr::App { app, env } => {
+                return Ok(Self::App(App {
+                    name: dotenv::expand(&app),
+                    env: env
+                        .into_iter()
+                        .map(|(key, value)| (key, dotenv::expand(&value)))
+                        .collect(),
+                }));
+            }
+            Repr::TermDir { termdir, terminal } => {
+                return Ok(Self::TermDir {
+                    dir: expand_path(&termdir),
+                    terminal,
+                });
+            }
+            Repr::Code { code, editor } => {
+                return Ok(Self::Code {
+                    path: expand_path(&code),
+                    editor,
+                });
+            }
+            Repr::Compose(compose) => return Ok(Self::Compose(compose)),
+            Repr::Play(play) => return Ok(Self::Play(play)),
+            Repr::Focus(focus) => return Ok(Self::Focus(focus)),
+            #[cfg(feature = "shell")]
+            Repr::Shell { sh, detach } => {
+                return Ok(Self::Shell {
+                    command: sh,
+                    detach,
+                });
+            }
+            #[cfg(feature = "shell")]
+            Repr::Pipeline { pipeline } => return Ok(Self::Pipeline { pipeline }),
+        };
+        s.parse()
+            .map_err(|e| serde::de::Error::custom(format!("{:#}", e)))
+    }
+}
+
+/// Entries written as strings, dispatched on their prefix.
+/// Entries of the config, and those given on the command line, are all parsed through this
+impl FromStr for Entry {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Shell commands are left for the shell to expand, with the variables in their environment
+        let s = if s.starts_with("sh:") || s.starts_with("pipe:") {
+            s.to_string()
+        } else {
+            dotenv::expand(s)
+        };
+        let entry = match s.split_once(':') {
+            #[cfg(feature = "shell")]
+            Some(("sh", command)) => Self::Shell {
+                command: command.to_string(),
+                detach: false,
+            },
+            #[cfg(feature = "shell")]
+            Some(("pipe", pipeline)) => Self::Pipeline {
+                pipeline: pipeline.split(" | ").map(str::to_string).collect(),
+            },
+            Some(("termdir", dir)) => Self::TermDir {
+                dir: expand_path(dir),
+                terminal: None,
+            },
+            Some(("code", path)) => Self::Code {
+                path: expand_path(path),
+                editor: None,
+            },
+            Some(("compose", path)) => Self::Compose(Compose {
+                path: expand_path(path),
+                profile: None,
+                detach: true,
+            }),
+            Some(("launchd", label)) => Self::Launchd(label.to_string()),
+            Some(("play", playlist)) => Self::Play(Play {
+                playlist: playlist.to_string(),
+                app: Player::default(),
+            }),
+            Some(("focus", mode)) => Self::Focus(Focus {
+                mode: mode.to_string(),
+                restore: false,
+                shortcut: None,
+            }),
+            Some(("km", macro_name)) => Self::KeyboardMaestro(macro_name.to_string()),
+            Some(("btt", trigger)) => Self::BetterTouchTool(trigger.to_string()),
+            Some(("meeting", "next")) => Self::NextMeeting,
+            Some(("mdfind", query)) => {
+                let query = query
+                    .strip_prefix('"')
+                    .and_then(|query| query.strip_suffix('"'))
+                    .unwrap_or(query);
+                Self::Spotlight(query.to_string())
+            }
+            Some(("ql", path)) => Self::QuickLook(expand_path(path)),
+            Some(("recent", rest)) => {
+                let (dir, query) = rest.split_once('?').unwrap_or((rest, ""));
+                let limit = match query.strip_prefix("limit=") {
+                    Some(limit) => limit
+                        .parse()
+                        .with_context(|| format!("invalid limit `{}`", limit))?,
+                    None => 1,
+                };
+                Self::Recent {
+                    dir: expand_path(dir),
+                    limit,
+                }
+            }
+            _ if s.starts_with(['/', '~']) => Self::File(expand_path(&s)),
+            Some(("file", rest)) if rest.starts_with("//") => {
+                // The `~` of `file://~/…` would be read as a host, so the rest of the path is decoded on its own
+                let rest = &rest[2..];
+                let (home, rest) = match rest.strip_prefix('~') {
+                    Some(rest) => (Some(expand_path("~")), rest),
+                    None => (None, rest),
+                };
+                let path = Url::parse(&format!("file://{}", rest))
+                    .ok()
+                    .and_then(|url| url.to_file_path().ok())
+                    .with_context(|| format!("invalid file URL `{}`", s))?;
+                Self::File(match home {
+                    Some(home) => home.join(path.strip_prefix("/").unwrap_or(&path)),
+                    None => path,
+                })
+            }
+            _ => match Url::parse(&s) {
+                Ok(url) => Self::Url(url),
+                Err(_) => Self::App(s.into()),
+            },
+        };
+        Ok(entry)
     }
 }
 
+/// The `limit` most recently modified files of the directory, most recent first
+fn recent_files(dir: &Path, limit: usize) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files = entries
+        .filter_map(Result::ok)
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            metadata
+                .is_file()
+                .then(|| (metadata.modified().ok(), entry.path()))
+        })
+        .collect::<Vec<_>>();
+    files.sort_by_key(|file| std::cmp::Reverse(file.0));
+    files
+        .into_iter()
+        .take(limit)
+        .map(|(_, path)| path)
+        .collect()
+}
+
+/// Expand variables and a leading `~` in a path of the config
+fn expand_path(path: &str) -> PathBuf {
+    shellexpand::tilde(&dotenv::expand(path))
+        .into_owned()
+        .into()
+}
+
 impl Display for Entry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::App(name) => write!(f, "{}", name),
+            Self::App(app) => write!(f, "{}", app.name),
             Self::File(path) => write!(f, "{}", path.to_string_lossy()),
             Self::Url(url) => write!(f, "{}", url),
             #[cfg(feature = "shell")]
-            Self::Shell(cmd) => write!(f, "{}", cmd),
+            Self::Shell { command, .. } => write!(f, "sh:{}", command),
+            #[cfg(feature = "shell")]
+            Self::Pipeline { pipeline } => write!(f, "pipe:{}", pipeline.join(" | ")),
+            Self::TermDir { dir, .. } => write!(f, "termdir:{}", dir.to_string_lossy()),
+            Self::Code { path, .. } => write!(f, "code:{}", path.to_string_lossy()),
+            Self::Compose(compose) => write!(f, "compose:{}", compose.path.to_string_lossy()),
+            Self::Launchd(label) => write!(f, "launchd:{}", label),
+            Self::Play(play) => write!(f, "play:{}", play.playlist),
+            Self::Focus(focus) => write!(f, "focus:{}", focus.mode),
+            Self::KeyboardMaestro(macro_name) => write!(f, "km:{}", macro_name),
+            Self::BetterTouchTool(trigger) => write!(f, "btt:{}", trigger),
+            Self::NextMeeting => write!(f, "meeting:next"),
+            Self::Recent { dir, limit } => {
+                write!(f, "recent:{}?limit={}", dir.to_string_lossy(), limit)
+            }
+            Self::Spotlight(query) => write!(f, "mdfind:\"{}\"", query),
+            Self::QuickLook(path) => write!(f, "ql:{}", path.to_string_lossy()),
         }
     }
 }
 
-impl Display for ParcelConfig {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (name, entries) in &self.parcels {
-            writeln!(f, "{}:", name)?;
-            for entry in entries {
-                writeln!(f, "- {}", entry)?;
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An entry of each type, written as its prefixed string
+    const STRINGS: &[&str] = &[
+        "Safari",
+        "/Users/me/Documents",
+        "https://example.com/?q=1",
+        "raycast://extensions/raycast/clipboard-history",
+        #[cfg(feature = "shell")]
+        "sh:npm run dev",
+        #[cfg(feature = "shell")]
+        "pipe:pbpaste | jq . | pbcopy",
+        "termdir:/Users/me/Projects/acme",
+        "code:/Users/me/Projects/acme",
+        "compose:/Users/me/Projects/acme",
+        "launchd:com.example.agent",
+        "play:Deep Focus",
+        "focus:Work",
+        "km:Arrange Windows",
+        "btt:tile-left",
+        "meeting:next",
+        "recent:/Users/me/Downloads?limit=3",
+        "mdfind:\"kMDItemDisplayName == 'Q3 Roadmap*'\"",
+        "ql:/Users/me/notes.md",
+    ];
+
+    /// Entries with options only their map form can hold
+    fn detailed() -> Vec<Entry> {
+        vec![
+            Entry::App(App {
+                name: "Zed".to_string(),
+                env: BTreeMap::from([("RUST_LOG".to_string(), "debug".to_string())]),
+            }),
+            #[cfg(feature = "shell")]
+            Entry::Shell {
+                command: "npm run dev".to_string(),
+                detach: true,
+            },
+            #[cfg(feature = "shell")]
+            Entry::Pipeline {
+                pipeline: vec![
+                    "pbpaste".to_string(),
+                    "jq .".to_string(),
+                    "pbcopy".to_string(),
+                ],
+            },
+            Entry::TermDir {
+                dir: PathBuf::from("/Users/me/Projects/acme"),
+                terminal: Some("iTerm".to_string()),
+            },
+            Entry::Code {
+                path: PathBuf::from("/Users/me/Projects/acme"),
+                editor: Some(Editor::Zed),
+            },
+            Entry::Compose(Compose {
+                path: PathBuf::from("/Users/me/Projects/acme"),
+                profile: Some("web".to_string()),
+                detach: false,
+            }),
+            Entry::Play(Play {
+                playlist: "spotify:playlist:37i9dQZF1DWZeKCadgRdKQ".to_string(),
+                app: Player::Spotify,
+            }),
+            Entry::Focus(Focus {
+                mode: "Work".to_string(),
+                restore: true,
+                shortcut: None,
+            }),
+        ]
+    }
+
+    #[test]
+    fn entries_parse_by_prefix() {
+        let cases = [
+            ("Safari", Entry::App("Safari".to_string().into())),
+            (
+                "/Users/me/notes.md",
+                Entry::File("/Users/me/notes.md".into()),
+            ),
+            (
+                "file:///Users/me/My%20Report.pdf",
+                Entry::File("/Users/me/My Report.pdf".into()),
+            ),
+            (
+                "https://example.com/",
+                Entry::Url(Url::parse("https://example.com/").unwrap()),
+            ),
+            #[cfg(feature = "shell")]
+            (
+                "sh:npm run dev",
+                Entry::Shell {
+                    command: "npm run dev".to_string(),
+                    detach: false,
+                },
+            ),
+            #[cfg(feature = "shell")]
+            (
+                "pipe:pbpaste | jq . | pbcopy",
+                Entry::Pipeline {
+                    pipeline: vec![
+                        "pbpaste".to_string(),
+                        "jq .".to_string(),
+                        "pbcopy".to_string(),
+                    ],
+                },
+            ),
+            (
+                "termdir:/Users/me/acme",
+                Entry::TermDir {
+                    dir: "/Users/me/acme".into(),
+                    terminal: None,
+                },
+            ),
+            (
+                "code:/Users/me/acme",
+                Entry::Code {
+                    path: "/Users/me/acme".into(),
+                    editor: None,
+                },
+            ),
+            (
+                "compose:/Users/me/acme",
+                Entry::Compose(Compose {
+                    path: "/Users/me/acme".into(),
+                    profile: None,
+                    detach: true,
+                }),
+            ),
+            (
+                "launchd:com.example.agent",
+                Entry::Launchd("com.example.agent".to_string()),
+            ),
+            (
+                "play:Deep Focus",
+                Entry::Play(Play {
+                    playlist: "Deep Focus".to_string(),
+                    app: Player::Music,
+                }),
+            ),
+            (
+                "focus:Work",
+                Entry::Focus(Focus {
+                    mode: "Work".to_string(),
+                    restore: false,
+                    shortcut: None,
+                }),
+            ),
+            ("km:Arrange", Entry::KeyboardMaestro("Arrange".to_string())),
+            (
+                "btt:tile-left",
+                Entry::BetterTouchTool("tile-left".to_string()),
+            ),
+            ("meeting:next", Entry::NextMeeting),
+            (
+                "recent:/Users/me/Downloads",
+                Entry::Recent {
+                    dir: "/Users/me/Downloads".into(),
+                    limit: 1,
+                },
+            ),
+            (
+                "recent:/Users/me/Downloads?limit=3",
+                Entry::Recent {
+                    dir: "/Users/me/Downloads".into(),
+                    limit: 3,
+                },
+            ),
+            (
+                "mdfind:\"kind:pdf\"",
+                Entry::Spotlight("kind:pdf".to_string()),
+            ),
+            ("mdfind:kind:pdf", Entry::Spotlight("kind:pdf".to_string())),
+            (
+                "ql:/Users/me/notes.md",
+                Entry::QuickLook("/Users/me/notes.md".into()),
+            ),
+        ];
+        for (s, expected) in cases {
+            assert_eq!(s.parse::<Entry>().unwrap(), expected, "{}", s);
+        }
+    }
+
+    #[test]
+    fn invalid_entries_fail_to_parse() {
+        let invalid = [
+            "recent:/Users/me/Downloads?limit=many",
+            "recent:/Users/me/Downloads?limit=-1",
+            "file://example.com/notes.md",
+        ];
+        for s in invalid {
+            assert!(s.parse::<Entry>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn entries_round_trip_through_their_string() {
+        for s in STRINGS {
+            let entry = s.parse::<Entry>().unwrap();
+            assert_eq!(entry.to_string(), *s);
+            assert_eq!(entry.to_string().parse::<Entry>().unwrap(), entry);
         }
-        Ok(())
+    }
+
+    #[test]
+    fn entries_round_trip_through_yaml() {
+        let entries = STRINGS.iter().map(|s| s.parse::<Entry>().unwrap());
+        for entry in entries.chain(detailed()) {
+            let yaml = serde_yaml::to_string(&entry).unwrap();
+            assert_eq!(
+                serde_yaml::from_str::<Entry>(&yaml).unwrap(),
+                entry,
+                "{}",
+                yaml
+            );
+        }
+    }
+
+    #[cfg(feature = "shell")]
+    #[test]
+    fn shell_entries_keep_their_prefix() {
+        let entry = Entry::Shell {
+            command: "open -a Safari".to_string(),
+            detach: false,
+        };
+        let yaml = serde_yaml::to_string(&entry).unwrap();
+        assert_eq!(
+            serde_yaml::from_str::<Value>(&yaml).unwrap(),
+            Value::String("sh:open -a Safari".to_string())
+        );
+    }
+
+    #[cfg(feature = "shell")]
+    #[test]
+    fn shell_entries_are_left_for_the_shell_to_expand() {
+        let shell = Entry::Shell {
+            command: "echo '$HOME'".to_string(),
+            detach: false,
+        };
+        assert_eq!("sh:echo '$HOME'".parse::<Entry>().unwrap(), shell);
+        assert_eq!(
+            serde_yaml::from_str::<Entry>("{sh: echo '$HOME'}").unwrap(),
+            shell
+        );
     }
 }
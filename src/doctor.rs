@@ -0,0 +1,106 @@
+//! Checks of the external tools entries and choosers rely on.
+
+use std::process::Command;
+
+use crate::{config::ParcelConfig, i18n::t, utils::command_exists};
+
+/// An optional tool, along with how to get it
+struct Tool {
+    program: &'static str,
+    /// Argument printing the version of the tool, if it has one
+    version_arg: Option<&'static str>,
+    install: &'static str,
+    /// Feature of the tool that needs it
+    used_by: &'static str,
+}
+
+const TOOLS: &[Tool] = &[
+    Tool {
+        program: "fzf",
+        version_arg: Some("--version"),
+        install: "brew install fzf",
+        used_by: "choose --chooser fzf",
+    },
+    Tool {
+        program: "bat",
+        version_arg: Some("--version"),
+        install: "brew install bat",
+        used_by: "highlighting in fzf previews",
+    },
+    Tool {
+        program: "tmux",
+        version_arg: Some("-V"),
+        install: "brew install tmux",
+        used_by: "the tmux chooser overlay",
+    },
+    Tool {
+        program: "docker",
+        version_arg: Some("--version"),
+        install: "brew install --cask docker",
+        used_by: "compose: entries",
+    },
+    Tool {
+        program: "icalBuddy",
+        version_arg: Some("-V"),
+        install: "brew install ical-buddy",
+        used_by: "meeting:next and the daemon",
+    },
+    Tool {
+        program: "shortcuts",
+        version_arg: None,
+        install: "macOS 12 or later",
+        used_by: "focus: entries",
+    },
+];
+
+/// First line printed by the program when asked for its version
+fn version(program: &str, arg: &str) -> Option<String> {
+    let output = Command::new(program).arg(arg).output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    text.lines()
+        .next()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+}
+
+/// Print whether every optional tool is installed, with its version or how to install it,
+/// and whether the programs run by the config's handlers exist.
+/// Returns whether nothing is missing
+pub fn run(config: &ParcelConfig) -> bool {
+    let mut healthy = true;
+    for tool in TOOLS {
+        if !command_exists(tool.program) {
+            healthy = false;
+            println!(
+                "✗ {}",
+                t!(
+                    "doctor-missing",
+                    program = tool.program,
+                    used_by = tool.used_by,
+                    install = tool.install
+                )
+            );
+            continue;
+        }
+        match tool.version_arg.and_then(|arg| version(tool.program, arg)) {
+            Some(version) => println!("✓ {} ({})", tool.program, version),
+            None => println!("✓ {}", tool.program),
+        }
+    }
+
+    for (prefix, command) in &config.handlers {
+        let Some(program) = command.split_whitespace().next() else {
+            continue;
+        };
+        if command_exists(program) {
+            println!("✓ {} ({})", program, prefix);
+        } else {
+            healthy = false;
+            println!(
+                "✗ {}",
+                t!("doctor-handler-missing", program = program, prefix = prefix)
+            );
+        }
+    }
+    healthy
+}
@@ -0,0 +1,55 @@
+//! Variables from a `.env` file, used to expand `${VAR}` in entries,
+//! and set in the environment of shell commands, which expand them themselves.
+
+use std::{collections::HashMap, env, fs, path::Path, sync::OnceLock};
+
+static VARS: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Load the variables of the `.env` file at `path`, if it exists.
+///
+/// Only the first call has any effect, and a missing file is the same as an empty one.
+pub fn load(path: &Path) {
+    VARS.get_or_init(|| {
+        let path = shellexpand::tilde(&path.to_string_lossy()).into_owned();
+        fs::read_to_string(path)
+            .map(|contents| parse(&contents))
+            .unwrap_or_default()
+    });
+}
+
+/// Parse `KEY=value` lines, ignoring blank lines and `#` comments.
+/// Lines can start with `export`, and values can be wrapped in single or double quotes
+fn parse(contents: &str) -> HashMap<String, String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            let value = [('"', '"'), ('\'', '\'')]
+                .iter()
+                .find_map(|&(open, close)| value.strip_prefix(open)?.strip_suffix(close))
+                .unwrap_or(value);
+            Some((key.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Value of a variable, from the `.env` file first and then from the environment
+pub fn var(name: &str) -> Option<String> {
+    VARS.get()
+        .and_then(|vars| vars.get(name).cloned())
+        .or_else(|| env::var(name).ok())
+}
+
+/// Variables of the `.env` file
+pub fn vars() -> impl Iterator<Item = (&'static String, &'static String)> {
+    VARS.get().into_iter().flatten()
+}
+
+/// Expand `$VAR` and `${VAR}` in `s`. Unknown variables are left as they are
+pub fn expand(s: &str) -> String {
+    shellexpand::env_with_context_no_errors(s, var).into_owned()
+}
@@ -0,0 +1,904 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+    io::{self, BufRead, IsTerminal, Write},
+    net::TcpStream,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+use crate::{
+    config::{Entry, MissingPath, Parcel, Step, WaitFor, duration},
+    error::ParcelError,
+    i18n::t,
+    interrupt,
+    opener::{self, Opener, Outcome},
+    placeholders,
+    plan::Plan,
+    processes::{self, Process},
+    state::Store,
+};
+
+/// How long `wait_for` conditions are waited for when they don't set a timeout
+const WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often `wait_for` conditions are checked
+const WAIT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long a launched app has to show up as running for its launch to be verified
+const LAUNCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often `open --wait` checks whether the parcel's apps and processes are still running
+const CLOSE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Most lines of stderr kept in results
+const STDERR_LINES: usize = 10;
+
+/// What happened to an entry, as printed by `open --report json`
+#[derive(Debug, Serialize)]
+pub struct EntryResult {
+    pub entry: String,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub status: Status,
+    pub duration_ms: u128,
+    /// The last lines the entry's command wrote to stderr, when it failed
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub stderr: String,
+    /// Whether a launched app was then seen running
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Finished,
+    Detached,
+    Failed,
+    Cancelled,
+    Skipped,
+    /// Left alone by an idempotent open, since it was already open
+    #[serde(rename = "already_open")]
+    AlreadyOpen,
+}
+
+impl EntryResult {
+    fn new(entry: &Entry, status: Status, duration: Duration, stderr: &str) -> Self {
+        let lines = stderr.trim_end().lines().collect::<Vec<_>>();
+        let excerpt = lines[lines.len().saturating_sub(STDERR_LINES)..].join("\n");
+        Self {
+            entry: placeholders::redact(&entry.to_string()),
+            kind: entry.kind(),
+            status,
+            duration_ms: duration.as_millis(),
+            stderr: placeholders::redact(&excerpt),
+            verified: None,
+        }
+    }
+}
+
+/// Error of an open interrupted with Ctrl-C, making the process exit with [`interrupt::EXIT_CODE`]
+#[derive(Debug)]
+pub struct Interrupted;
+
+impl Display for Interrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", t!("parcel-interrupted"))
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+/// What previous opens of the parcel left behind, and where the progress of this open is saved
+struct Progress {
+    store: Store<Vec<String>>,
+    /// Entries opened by the interrupted open being resumed, then by this one
+    opened: Vec<String>,
+    /// Processes still running from previous opens, when skipping the entries already open
+    tracked: Option<Vec<Process>>,
+}
+
+impl Progress {
+    fn load(plan: &Plan) -> io::Result<Self> {
+        let store = Store::state(Path::new("progress").join(plan.parcel.replace('/', "%2F")));
+        let opened = if plan.resume {
+            store.load()?.unwrap_or_default()
+        } else {
+            store.remove()?;
+            Vec::new()
+        };
+        let tracked = if plan.idempotent {
+            Some(processes::running(plan.parcel)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            store,
+            opened,
+            tracked,
+        })
+    }
+
+    /// Whether the interrupted open being resumed already opened the entry
+    fn resumed(&self, entry: &Entry) -> bool {
+        self.opened.contains(&saved(entry))
+    }
+}
+
+/// What happened to the entries of a plan that didn't simply finish
+#[derive(Default)]
+struct Report<'a> {
+    cancelled: Vec<&'a Entry>,
+    failed: Vec<Failure<'a>>,
+    /// Critical entry whose failure stopped the open, if any
+    aborted_by: Option<&'a Entry>,
+    /// Entries left unopened because of it
+    skipped: Vec<&'a Entry>,
+    /// Entries left unopened by an idempotent open
+    already_open: Vec<&'a Entry>,
+    /// Entries left unopened since the open being resumed opened them
+    resumed: Vec<&'a Entry>,
+    /// Entries opened successfully, which can be closed again when interrupted
+    opened: Vec<&'a Entry>,
+    /// Entries left unopened because of Ctrl-C
+    interrupted: Vec<&'a Entry>,
+    /// Apps launched and then seen running
+    verified: usize,
+    /// Apps launched but never seen running, which may have crashed right away
+    unverified: Vec<&'a Entry>,
+    detached: Vec<Process>,
+    results: Vec<EntryResult>,
+}
+
+struct Failure<'a> {
+    step: &'a Step,
+    reason: String,
+}
+
+impl Report<'_> {
+    /// Whether no entry was cancelled, and every entry that failed was optional
+    fn succeeded(&self) -> bool {
+        self.cancelled.is_empty()
+            && self
+                .failed
+                .iter()
+                .all(|failure| failure.step.options.optional)
+    }
+}
+
+/// Open every entry of a plan.
+///
+/// Stages are opened in order, each one only after the previous one has finished.
+/// Stages opened on success, like `then`, are skipped if any entry before them failed or was cancelled.
+/// Failed entries are reported, and fail the whole open unless they are optional.
+/// A critical entry failing skips every entry after it.
+/// Once the plan's timeout is reached, the entries still opening are killed and the remaining ones skipped,
+/// and all of them are reported.
+/// Detached entries aren't waited for, their processes are recorded so that `stop` can terminate them.
+/// Idempotent plans skip the entries that are already open, see [`already_open`].
+/// Ctrl-C stops opening entries, and offers to close the ones already opened.
+/// The entries opened are saved as they open, so that a resumed plan skips them after an interruption or failure.
+pub fn run(plan: &Plan, opener: &dyn Opener) -> anyhow::Result<()> {
+    run_with_results(plan, opener, &mut Vec::new())
+}
+
+/// Open every entry of a plan like [`run`], adding what happened to each entry to `results`
+pub fn run_with_results(
+    plan: &Plan,
+    opener: &dyn Opener,
+    results: &mut Vec<EntryResult>,
+) -> anyhow::Result<()> {
+    interrupt::install();
+    let deadline = plan.timeout.map(|timeout| Instant::now() + timeout);
+    let mut progress = Progress::load(plan)?;
+    let mut report = Report::default();
+    for stage in &plan.stages {
+        if stage.on_success && !report.succeeded() {
+            eprintln!(
+                "{}",
+                t!("stage-skipped", stage = stage.name.unwrap_or_default())
+            );
+            report.results.extend(
+                stage
+                    .entries
+                    .iter()
+                    .map(|step| EntryResult::new(&step.entry, Status::Skipped, Duration::ZERO, "")),
+            );
+            continue;
+        }
+        open_all(
+            &stage.entries,
+            stage.limit,
+            plan,
+            deadline,
+            opener,
+            &mut progress,
+            &mut report,
+        )?;
+    }
+    results.append(&mut report.results);
+    processes::record(plan.parcel, &report.detached)?;
+
+    if interrupt::interrupted() {
+        for entry in &report.interrupted {
+            let entry = placeholders::redact(&entry.to_string());
+            eprintln!("{}", t!("entry-skipped", entry = entry));
+        }
+        offer_to_close(&report, opener)?;
+        return Err(Interrupted.into());
+    }
+
+    if !report.unverified.is_empty() {
+        eprintln!(
+            "{}",
+            t!(
+                "launches-unverified",
+                verified = report.verified,
+                total = report.verified + report.unverified.len()
+            )
+        );
+        for entry in &report.unverified {
+            let entry = placeholders::redact(&entry.to_string());
+            eprintln!("  {}", entry);
+        }
+    }
+
+    if !report.resumed.is_empty() {
+        eprintln!("{}", t!("entries-resumed", count = report.resumed.len()));
+    }
+
+    if !report.already_open.is_empty() {
+        eprintln!(
+            "{}",
+            t!("entries-already-open", count = report.already_open.len())
+        );
+        for entry in &report.already_open {
+            let entry = placeholders::redact(&entry.to_string());
+            eprintln!("  {}", entry);
+        }
+    }
+
+    for failure in &report.failed {
+        let entry = placeholders::redact(&failure.step.entry.to_string());
+        let reason = placeholders::redact(&failure.reason);
+        if failure.step.options.optional {
+            eprintln!(
+                "{}",
+                t!("entry-failed-optional", entry = entry, reason = reason)
+            );
+        } else {
+            eprintln!("{}", t!("entry-failed", entry = entry, reason = reason));
+        }
+    }
+
+    if let Some(timeout) = plan.timeout
+        && !report.cancelled.is_empty()
+    {
+        for entry in report.cancelled {
+            let entry = placeholders::redact(&entry.to_string());
+            eprintln!("{}", t!("entry-cancelled", entry = entry));
+        }
+        return Err(ParcelError::TimedOut {
+            name: plan.parcel.to_string(),
+            timeout,
+        }
+        .into());
+    }
+
+    if let Some(critical) = report.aborted_by {
+        for entry in report.skipped {
+            let entry = placeholders::redact(&entry.to_string());
+            eprintln!("{}", t!("entry-skipped", entry = entry));
+        }
+        let reason = report
+            .failed
+            .iter()
+            .find(|failure| std::ptr::eq(&failure.step.entry, critical))
+            .map(|failure| placeholders::redact(&failure.reason))
+            .unwrap_or_default();
+        let failed = ParcelError::EntryFailed {
+            entry: placeholders::redact(&critical.to_string()),
+            source: io::Error::other(reason),
+        };
+        return Err(anyhow::Error::new(failed).context(t!("parcel-aborted", name = plan.parcel)));
+    }
+
+    let failed = report
+        .failed
+        .iter()
+        .filter(|failure| !failure.step.options.optional)
+        .count();
+    if failed > 0 {
+        return Err(ParcelError::ParcelFailed {
+            name: plan.parcel.to_string(),
+            count: failed,
+        }
+        .into());
+    }
+    progress.store.remove()?;
+    Ok(())
+}
+
+/// Ask whether to close the entries opened before Ctrl-C, and close them along with their detached processes
+fn offer_to_close(report: &Report, opener: &dyn Opener) -> io::Result<()> {
+    if report.opened.is_empty() || !io::stdin().is_terminal() {
+        return Ok(());
+    }
+    eprint!("{} ", t!("interrupted-close", count = report.opened.len()));
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+        return Ok(());
+    }
+
+    for entry in report.opened.iter().rev() {
+        let _ = opener.close(entry);
+    }
+    let pids = report
+        .detached
+        .iter()
+        .map(|process| process.pid)
+        .collect::<Vec<_>>();
+    processes::terminate(&pids);
+    Ok(())
+}
+
+/// Close the entries of a parcel that can be closed, in the reverse order they were opened
+pub fn close(parcel: &Parcel, opener: &dyn Opener) {
+    let entries = parcel.entries().collect::<Vec<_>>();
+    for entry in entries.into_iter().rev() {
+        let _ = opener.close(entry);
+    }
+}
+
+/// Open the entry, opening it again up to `retries` times while it fails.
+/// Each attempt is abandoned after `timeout`, and fails
+fn open(
+    entry: &Entry,
+    retries: u32,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+    opener: &dyn Opener,
+) -> io::Result<Outcome> {
+    let attempt = || {
+        let Some(timeout) = timeout else {
+            return opener.open(entry, deadline);
+        };
+        let limit = Instant::now() + timeout;
+        match opener.open(
+            entry,
+            Some(deadline.map_or(limit, |deadline| deadline.min(limit))),
+        ) {
+            // Cancelled by the entry's own timeout rather than the plan's
+            Ok(Outcome::Cancelled) if deadline.is_none_or(|deadline| Instant::now() < deadline) => {
+                Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    t!("entry-timed-out", timeout = duration::format(timeout)),
+                ))
+            }
+            outcome => outcome,
+        }
+    };
+
+    let mut outcome = attempt();
+    for _ in 0..retries {
+        if interrupt::interrupted() || !matches!(outcome, Err(_) | Ok(Outcome::Failed { .. })) {
+            break;
+        }
+        outcome = attempt();
+    }
+    outcome
+}
+
+/// Wait until the condition is met, up to its timeout or the deadline. Returns whether it was met
+fn wait_for(condition: &WaitFor, deadline: Option<Instant>) -> bool {
+    let limit = Instant::now() + condition.timeout.unwrap_or(WAIT_TIMEOUT);
+    let limit = deadline.map_or(limit, |deadline| deadline.min(limit));
+    loop {
+        let process_ready = condition
+            .process
+            .as_deref()
+            .is_none_or(processes::is_running_named);
+        let port_ready = condition
+            .port
+            .is_none_or(|port| TcpStream::connect(("127.0.0.1", port)).is_ok());
+        if process_ready && port_ready {
+            return true;
+        }
+        if Instant::now() >= limit || interrupt::interrupted() {
+            return false;
+        }
+        thread::sleep(WAIT_INTERVAL);
+    }
+}
+
+/// Block until the apps of the parcel have quit and the processes its entries detached have exited,
+/// for `open --wait`. Other entries, like files and URLs, can't be waited for.
+/// Stops waiting when interrupted
+pub fn wait_until_closed(name: &str, parcel: &Parcel) -> anyhow::Result<()> {
+    let apps = parcel
+        .entries()
+        .filter_map(|entry| match entry {
+            Entry::App(app) => Some(app.name.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    interrupt::install();
+    loop {
+        if interrupt::interrupted() {
+            return Err(Interrupted.into());
+        }
+        let running = apps.iter().any(|app| opener::is_app_running(app))
+            || !processes::running(name)?.is_empty();
+        if !running {
+            return Ok(());
+        }
+        thread::sleep(CLOSE_INTERVAL);
+    }
+}
+
+/// Whether the entry is already open: the process it detached on a previous open still running,
+/// or open as the opener sees it, see [`Opener::is_open`]
+fn already_open(entry: &Entry, tracked: &[Process], opener: &dyn Opener) -> bool {
+    if entry.detached() {
+        let entry = saved(entry);
+        return tracked.iter().any(|process| process.entry == entry);
+    }
+    opener.is_open(entry)
+}
+
+/// Wait for a launched app to show up as running, up to [`LAUNCH_TIMEOUT`]. Returns whether it did
+fn verify_launch(entry: &Entry, opener: &dyn Opener) -> bool {
+    let limit = Instant::now() + LAUNCH_TIMEOUT;
+    loop {
+        if opener.is_open(entry) {
+            return true;
+        }
+        if Instant::now() >= limit || interrupt::interrupted() {
+            return false;
+        }
+        thread::sleep(WAIT_INTERVAL);
+    }
+}
+
+/// Open the entry with its retries, then wait for its `wait_for` condition if it has one
+fn open_step(
+    step: &Step,
+    retries: u32,
+    deadline: Option<Instant>,
+    opener: &dyn Opener,
+) -> io::Result<Outcome> {
+    let entry = &step.entry;
+    let outcome = open(entry, retries, step.options.timeout, deadline, opener)?;
+    match &step.options.wait_for {
+        Some(condition)
+            if matches!(outcome, Outcome::Finished | Outcome::Detached(_))
+                && !wait_for(condition, deadline) =>
+        {
+            Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                t!("wait-timed-out", entry = entry),
+            ))
+        }
+        _ => Ok(outcome),
+    }
+}
+
+/// Split the steps into chains, each opened from start to end by one worker.
+/// The steps of a group make up one chain, in the order they are written, and every other step is a chain of its own
+fn chains<'a>(steps: &[&'a Step]) -> Vec<Vec<&'a Step>> {
+    let mut chains: Vec<Vec<&Step>> = Vec::new();
+    let mut groups: HashMap<&str, usize> = HashMap::new();
+    for &step in steps {
+        match step.options.group.as_deref() {
+            Some(group) => match groups.get(group) {
+                Some(&i) => chains[i].push(step),
+                None => {
+                    groups.insert(group, chains.len());
+                    chains.push(vec![step]);
+                }
+            },
+            None => chains.push(vec![step]),
+        }
+    }
+    chains
+}
+
+/// Open entries with at most `limit` of them in flight at once, and the ones of a group one at a time.
+/// Each group opens at its own pace, so a slow entry only holds back the entries of its group.
+/// Failed entries are retried as many times as their options say, or as many as the plan's by default.
+/// The entries opened by the open being resumed are skipped, and so are the ones already open for an idempotent open.
+/// File entries whose path doesn't exist are skipped or failed, as `missing_path` says.
+/// The entries opened are added to the progress, saved after each of them
+fn open_all<'a>(
+    steps: &[&'a Step],
+    limit: usize,
+    plan: &Plan,
+    deadline: Option<Instant>,
+    opener: &dyn Opener,
+    progress: &mut Progress,
+    report: &mut Report<'a>,
+) -> io::Result<()> {
+    let chains = chains(steps);
+    let workers = limit.clamp(1, chains.len().max(1));
+    let chains = Mutex::new(VecDeque::from(chains));
+    // Only read while the entries open, so it's left out of the lock
+    let tracked = progress.tracked.take();
+    let shared = Mutex::new((&mut *progress, report));
+    let opened = thread::scope(|s| {
+        let handles = (0..workers)
+            .map(|_| {
+                s.spawn(|| -> io::Result<()> {
+                    loop {
+                        let next = chains.lock().unwrap().pop_front();
+                        let Some(chain) = next else {
+                            return Ok(());
+                        };
+                        for step in chain {
+                            open_one(step, plan, deadline, opener, tracked.as_deref(), &shared)?;
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .try_for_each(|handle| handle.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+    });
+    progress.tracked = tracked;
+    opened
+}
+
+/// Open a step, or record why it is left unopened
+fn open_one<'a>(
+    step: &'a Step,
+    plan: &Plan,
+    deadline: Option<Instant>,
+    opener: &dyn Opener,
+    tracked: Option<&[Process]>,
+    shared: &Mutex<(&mut Progress, &mut Report<'a>)>,
+) -> io::Result<()> {
+    {
+        let mut shared = shared.lock().unwrap();
+        let (progress, report) = &mut *shared;
+        let unopened = if interrupt::interrupted() {
+            Some((&mut report.interrupted, Status::Cancelled))
+        } else if report.aborted_by.is_some() {
+            Some((&mut report.skipped, Status::Skipped))
+        } else if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            Some((&mut report.cancelled, Status::Cancelled))
+        } else {
+            None
+        };
+        if let Some((entries, status)) = unopened {
+            entries.push(&step.entry);
+            report
+                .results
+                .push(EntryResult::new(&step.entry, status, Duration::ZERO, ""));
+            return Ok(());
+        }
+
+        if progress.resumed(&step.entry) {
+            report.resumed.push(&step.entry);
+            report.results.push(EntryResult::new(
+                &step.entry,
+                Status::Skipped,
+                Duration::ZERO,
+                "",
+            ));
+            return Ok(());
+        }
+
+        if let Entry::File(path) = &step.entry
+            && !path.exists()
+        {
+            let missing_path = step.options.missing_path.unwrap_or(plan.missing_path);
+            if missing_path == MissingPath::Error {
+                report.results.push(EntryResult::new(
+                    &step.entry,
+                    Status::Failed,
+                    Duration::ZERO,
+                    "",
+                ));
+                if step.options.critical {
+                    report.aborted_by.get_or_insert(&step.entry);
+                }
+                report.failed.push(Failure {
+                    step,
+                    reason: t!("entry-missing"),
+                });
+                return Ok(());
+            }
+            if missing_path == MissingPath::Warn {
+                eprintln!(
+                    "{}",
+                    t!("entry-missing-skipped", entry = saved(&step.entry))
+                );
+            }
+            report.results.push(EntryResult::new(
+                &step.entry,
+                Status::Skipped,
+                Duration::ZERO,
+                "",
+            ));
+            return Ok(());
+        }
+    }
+
+    if tracked.is_some_and(|tracked| already_open(&step.entry, tracked, opener)) {
+        let mut shared = shared.lock().unwrap();
+        let (_, report) = &mut *shared;
+        report.already_open.push(&step.entry);
+        report.results.push(EntryResult::new(
+            &step.entry,
+            Status::AlreadyOpen,
+            Duration::ZERO,
+            "",
+        ));
+        return Ok(());
+    }
+
+    let retries = step.options.retries.unwrap_or(plan.retries);
+    let start = Instant::now();
+    let opened = panic::catch_unwind(AssertUnwindSafe(|| {
+        let outcome = open_step(step, retries, deadline, opener);
+        let verified = match (&outcome, &step.entry) {
+            (Ok(Outcome::Finished), Entry::App(_)) => Some(verify_launch(&step.entry, opener)),
+            _ => None,
+        };
+        (outcome, verified)
+    }));
+    let duration = start.elapsed();
+    let (outcome, verified) = match opened {
+        Ok((outcome, verified)) => (Some(outcome), verified),
+        Err(_) => (None, None),
+    };
+
+    let mut shared = shared.lock().unwrap();
+    let (progress, report) = &mut *shared;
+    let result = |status, stderr: &str| EntryResult::new(&step.entry, status, duration, stderr);
+    let reason = match outcome {
+        Some(Ok(Outcome::Detached(pid))) => {
+            report.detached.push(Process {
+                pid,
+                entry: saved(&step.entry),
+            });
+            report.results.push(result(Status::Detached, ""));
+            return opened_entry(step, progress, report);
+        }
+        Some(Ok(Outcome::Cancelled)) => {
+            report.cancelled.push(&step.entry);
+            report.results.push(result(Status::Cancelled, ""));
+            return Ok(());
+        }
+        Some(Ok(Outcome::Finished)) => {
+            match verified {
+                Some(true) => report.verified += 1,
+                Some(false) => report.unverified.push(&step.entry),
+                None => {}
+            }
+            report.results.push(EntryResult {
+                verified,
+                ..result(Status::Finished, "")
+            });
+            return opened_entry(step, progress, report);
+        }
+        Some(Ok(Outcome::Failed { status, stderr })) => {
+            report.results.push(result(Status::Failed, &stderr));
+            status.to_string()
+        }
+        Some(Err(e)) => {
+            report.results.push(result(Status::Failed, ""));
+            e.to_string()
+        }
+        None => {
+            report.results.push(result(Status::Failed, ""));
+            t!("entry-panicked")
+        }
+    };
+    if step.options.critical {
+        report.aborted_by.get_or_insert(&step.entry);
+    }
+    report.failed.push(Failure { step, reason });
+    Ok(())
+}
+
+/// Record an entry as opened, and save the progress so that a resumed open skips it
+fn opened_entry<'a>(
+    step: &'a Step,
+    progress: &mut Progress,
+    report: &mut Report<'a>,
+) -> io::Result<()> {
+    report.opened.push(&step.entry);
+    progress.opened.push(saved(&step.entry));
+    progress.store.save(&progress.opened)
+}
+
+/// The entry as saved in the state directory, without the passwords typed for its placeholders
+fn saved(entry: &Entry) -> String {
+    placeholders::redact(&entry.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Settings, opener::Recorder};
+
+    fn parcel(yaml: &str) -> Parcel {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn strings(entries: &Mutex<Vec<Entry>>) -> Vec<String> {
+        entries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(Entry::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn opens_the_phases_in_order() {
+        let parcel = parcel(
+            "
+            phases:
+              setup: [km:Start]
+              apps: [https://example.com/, https://example.org/]
+            then: [btt:Done]
+            ",
+        );
+        let recorder = Recorder::default();
+        let plan = Plan::new("engine-test-phases", &parcel, &Settings::default());
+        run(&plan, &recorder).unwrap();
+        assert_eq!(
+            strings(&recorder.opened),
+            [
+                "km:Start",
+                "https://example.com/",
+                "https://example.org/",
+                "btt:Done"
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_missing_paths_when_told_to() {
+        let parcel = parcel(
+            "
+            - entry: /kozutsumi/does/not/exist
+              missing_path: skip
+            - km:Next
+            ",
+        );
+        let recorder = Recorder::default();
+        let plan = Plan::new("engine-test-missing", &parcel, &Settings::default());
+        run(&plan, &recorder).unwrap();
+        assert_eq!(strings(&recorder.opened), ["km:Next"]);
+    }
+
+    #[test]
+    fn idempotent_opens_skip_what_the_opener_sees_open() {
+        let parcel = parcel("[Zed, km:Next]");
+        let recorder = Recorder::default();
+        recorder.opened.lock().unwrap().push("Zed".parse().unwrap());
+        let mut plan = Plan::new("engine-test-idempotent", &parcel, &Settings::default());
+        plan.idempotent = true;
+        let mut results = Vec::new();
+        run_with_results(&plan, &recorder, &mut results).unwrap();
+
+        assert_eq!(strings(&recorder.opened), ["Zed", "km:Next"]);
+        let statuses = results
+            .iter()
+            .map(|result| (result.entry.as_str(), result.status))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [("Zed", Status::AlreadyOpen), ("km:Next", Status::Finished)]
+        );
+    }
+
+    #[test]
+    fn launched_apps_are_verified_through_the_opener() {
+        let parcel = parcel("[Zed]");
+        let recorder = Recorder::default();
+        let plan = Plan::new("engine-test-verify", &parcel, &Settings::default());
+        let mut results = Vec::new();
+        run_with_results(&plan, &recorder, &mut results).unwrap();
+        assert_eq!(results[0].verified, Some(true));
+    }
+
+    #[test]
+    fn closes_in_reverse_order() {
+        let parcel = parcel("[km:First, km:Second, btt:Third]");
+        let recorder = Recorder::default();
+        close(&parcel, &recorder);
+        assert_eq!(
+            strings(&recorder.closed),
+            ["btt:Third", "km:Second", "km:First"]
+        );
+        assert!(recorder.opened.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn each_group_is_a_chain_of_its_own() {
+        let parcel = parcel(
+            "
+            - entry: km:A
+              group: vpn
+            - km:B
+            - entry: km:C
+              group: vpn
+            - km:D
+            ",
+        );
+        let steps = parcel.entries.iter().collect::<Vec<_>>();
+        let chains = chains(&steps)
+            .into_iter()
+            .map(|chain| {
+                chain
+                    .iter()
+                    .map(|step| step.entry.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(chains, [vec!["km:A", "km:C"], vec!["km:B"], vec!["km:D"]]);
+    }
+
+    /// Takes its time opening `km:Slow`
+    struct Slow(Recorder);
+
+    impl Opener for Slow {
+        fn open(&self, entry: &Entry, deadline: Option<Instant>) -> io::Result<Outcome> {
+            if entry.to_string() == "km:Slow" {
+                thread::sleep(Duration::from_millis(300));
+            }
+            self.0.open(entry, deadline)
+        }
+
+        fn close(&self, entry: &Entry) -> io::Result<()> {
+            self.0.close(entry)
+        }
+
+        fn describe(&self, entry: &Entry) -> String {
+            self.0.describe(entry)
+        }
+
+        fn is_open(&self, entry: &Entry) -> bool {
+            self.0.is_open(entry)
+        }
+    }
+
+    #[test]
+    fn a_slow_group_does_not_hold_back_the_others() {
+        let parcel = parcel(
+            "
+            - entry: km:Slow
+              group: a
+            - entry: km:A2
+              group: a
+            - entry: km:B1
+              group: b
+            - entry: km:B2
+              group: b
+            ",
+        );
+        let slow = Slow(Recorder::default());
+        let settings = Settings {
+            concurrency: Some(2),
+            ..Default::default()
+        };
+        let plan = Plan::new("engine-test-slow-group", &parcel, &settings);
+        run(&plan, &slow).unwrap();
+        assert_eq!(
+            strings(&slow.0.opened),
+            ["km:B1", "km:B2", "km:Slow", "km:A2"]
+        );
+    }
+}
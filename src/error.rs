@@ -0,0 +1,73 @@
+//! Failures of loading the config and opening parcels, for the callers that act on their kind.
+
+use std::{
+    error::Error,
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{config::duration, i18n::t};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ParcelError {
+    /// The config, or a file it includes, doesn't exist or can't be read
+    #[error("{}", t!("config-unreadable", path = .path.display()))]
+    ConfigNotFound {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// The config doesn't parse, or doesn't describe valid parcels
+    #[error("{}", t!("config-invalid", path = .location))]
+    ParseError {
+        /// Path of the file, followed by the line and column of the error when they are known
+        location: String,
+        #[source]
+        source: Box<dyn Error + Send + Sync>,
+    },
+    #[error("{}", t!("parcel-not-found", name = .name, suggestions = .suggestions.join(", ")))]
+    ParcelNotFound {
+        name: String,
+        /// Parcels a looser `settings.matching` would have found, sorted, or all of them if none would
+        suggestions: Vec<String>,
+    },
+    /// Several parcels match a shorter form of their name, see `settings.matching`
+    #[error("{}", t!("parcel-ambiguous", name = .name, matches = .matches.join(", ")))]
+    ParcelAmbiguous { name: String, matches: Vec<String> },
+    /// An entry failed to open
+    #[error("{}", t!("entry-open-failed", entry = .entry))]
+    EntryFailed {
+        entry: String,
+        #[source]
+        source: io::Error,
+    },
+    /// Entries of the parcel that aren't optional failed to open
+    #[error("{}", t!("parcel-failed", name = .name, count = .count))]
+    ParcelFailed { name: String, count: usize },
+    /// Entries of the parcel were still opening at its timeout, and were cancelled
+    #[error("{}", t!("parcel-timed-out", name = .name, timeout = duration::format(*.timeout)))]
+    TimedOut { name: String, timeout: Duration },
+}
+
+impl ParcelError {
+    /// An invalid config, located at its path
+    pub fn invalid(path: &Path, source: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+        Self::ParseError {
+            location: path.display().to_string(),
+            source: source.into(),
+        }
+    }
+
+    /// An invalid YAML config, located at the line and column of the error
+    pub fn invalid_yaml(path: &Path, source: serde_yaml::Error) -> Self {
+        let location = match source.location() {
+            Some(at) => format!("{}:{}:{}", path.display(), at.line(), at.column()),
+            None => path.display().to_string(),
+        };
+        Self::ParseError {
+            location,
+            source: source.into(),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! Export of the relationships between parcels and their entries, as a graph.
+
+use std::fmt::Write as _;
+
+use clap::ValueEnum;
+
+use crate::config::ParcelConfig;
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz, e.g. `kozutsumi graph | dot -Tsvg > parcels.svg`
+    #[default]
+    Dot,
+    /// Mermaid flowchart, which renders in Markdown on GitHub
+    Mermaid,
+}
+
+/// The graph linking each parcel to its entries.
+///
+/// Entries are nodes of their own, so an entry shared by several parcels links to all of them.
+pub fn render(config: &ParcelConfig, format: GraphFormat) -> String {
+    let mut parcels = config.parcels.iter().collect::<Vec<_>>();
+    parcels.sort_by_key(|(name, _)| name.as_str());
+
+    let mut entries = Vec::<String>::new();
+    let mut edges = Vec::new();
+    for (i, (_, parcel)) in parcels.iter().enumerate() {
+        for entry in parcel.entries() {
+            let entry = entry.to_string();
+            let j = entries
+                .iter()
+                .position(|known| *known == entry)
+                .unwrap_or_else(|| {
+                    entries.push(entry);
+                    entries.len() - 1
+                });
+            if !edges.contains(&(i, j)) {
+                edges.push((i, j));
+            }
+        }
+    }
+
+    let mut out = String::new();
+    match format {
+        GraphFormat::Dot => {
+            let _ = writeln!(out, "digraph parcels {{");
+            let _ = writeln!(out, "    rankdir=LR;");
+            for (i, (name, _)) in parcels.iter().enumerate() {
+                let _ = writeln!(out, "    p{} [label={:?}, shape=box];", i, name);
+            }
+            for (j, entry) in entries.iter().enumerate() {
+                let _ = writeln!(out, "    e{} [label={:?}];", j, entry);
+            }
+            for (i, j) in edges {
+                let _ = writeln!(out, "    p{} -> e{};", i, j);
+            }
+            let _ = writeln!(out, "}}");
+        }
+        GraphFormat::Mermaid => {
+            let escape = |text: &str| text.replace('"', "#quot;");
+            let _ = writeln!(out, "flowchart LR");
+            for (i, (name, _)) in parcels.iter().enumerate() {
+                let _ = writeln!(out, "    p{}[\"{}\"]", i, escape(name));
+            }
+            for (j, entry) in entries.iter().enumerate() {
+                let _ = writeln!(out, "    e{}(\"{}\")", j, escape(entry));
+            }
+            for (i, j) in edges {
+                let _ = writeln!(out, "    p{} --> e{}", i, j);
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,121 @@
+//! History of the parcels opened, kept in a SQLite database in the state directory.
+//!
+//! The schema is upgraded on open by applying the [`MIGRATIONS`] newer than the database's `user_version`.
+
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rusqlite::{Connection, params};
+use serde::Serialize;
+
+use crate::state;
+
+/// Statements upgrading the schema, the database's `user_version` being how many were applied
+const MIGRATIONS: &[&str] = &["CREATE TABLE opens (
+        id INTEGER PRIMARY KEY,
+        parcel TEXT NOT NULL,
+        opened_at INTEGER NOT NULL,
+        duration_ms INTEGER NOT NULL,
+        succeeded INTEGER NOT NULL
+    );
+    CREATE INDEX opens_parcel ON opens (parcel, opened_at);"];
+
+pub fn path() -> PathBuf {
+    state::state_dir().join("history.sqlite3")
+}
+
+pub struct History {
+    connection: Connection,
+}
+
+/// How often a parcel was opened and how long it took
+#[derive(Debug, Default, Serialize)]
+pub struct Stats {
+    pub opens: u64,
+    /// Opens that didn't open every entry
+    pub failed: u64,
+    /// When the parcel was opened most recently, in seconds since the Unix epoch
+    pub last_opened: Option<u64>,
+    pub average_ms: Option<u64>,
+}
+
+impl History {
+    /// Open the database, creating it and applying pending migrations if needed
+    pub fn open() -> anyhow::Result<Self> {
+        let path = path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut connection = Connection::open(path)?;
+        // Parcels chosen together are opened side by side, and recorded at about the same time
+        connection.busy_timeout(Duration::from_secs(5))?;
+        migrate(&mut connection)?;
+        Ok(Self { connection })
+    }
+
+    /// Record an open of the parcel, started at `opened_at` and taking `duration`
+    pub fn record(
+        &self,
+        parcel: &str,
+        opened_at: SystemTime,
+        duration: Duration,
+        succeeded: bool,
+    ) -> anyhow::Result<()> {
+        let opened_at = opened_at.duration_since(UNIX_EPOCH)?.as_secs();
+        self.connection.execute(
+            "INSERT INTO opens (parcel, opened_at, duration_ms, succeeded) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                parcel,
+                opened_at as i64,
+                duration.as_millis() as i64,
+                succeeded
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The parcel opened most recently, if any
+    pub fn last(&self) -> anyhow::Result<Option<String>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT parcel FROM opens ORDER BY opened_at DESC, id DESC LIMIT 1")?;
+        let mut rows = statement.query_map([], |row| row.get(0))?;
+        Ok(rows.next().transpose()?)
+    }
+
+    /// How often the parcel was opened, when it was last, and how long its opens took on average
+    pub fn stats(&self, parcel: &str) -> anyhow::Result<Stats> {
+        Ok(self.connection.query_row(
+            "SELECT COUNT(*), COUNT(*) - COALESCE(SUM(succeeded), 0), MAX(opened_at), AVG(duration_ms)
+            FROM opens WHERE parcel = ?1",
+            [parcel],
+            |row| {
+                Ok(Stats {
+                    opens: row.get::<_, i64>(0)? as u64,
+                    failed: row.get::<_, i64>(1)? as u64,
+                    last_opened: row.get::<_, Option<i64>>(2)?.map(|secs| secs as u64),
+                    average_ms: row.get::<_, Option<f64>>(3)?.map(|ms| ms.round() as u64),
+                })
+            },
+        )?)
+    }
+
+    /// Rebuild the database file, reclaiming the space left by deleted rows
+    pub fn vacuum(&self) -> anyhow::Result<()> {
+        self.connection.execute_batch("VACUUM")?;
+        Ok(())
+    }
+}
+
+fn migrate(connection: &mut Connection) -> rusqlite::Result<()> {
+    let version: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    let transaction = connection.transaction()?;
+    for (applied, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", applied + 1)?;
+    }
+    transaction.commit()
+}
@@ -0,0 +1,92 @@
+//! Shell integration suggesting the parcels of a project when entering its directory.
+//!
+//! Projects keep their parcels in a `.kozutsumi.yml` at their root, a config like the main one.
+
+use std::{env, path::Path};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::{OpenArgs, ParcelCommands},
+    config::ParcelConfig,
+    i18n::t,
+    opener::Opener,
+};
+
+/// Name of the config of a project, looked for in the directories the shell enters
+const PROJECT_CONFIG: &str = ".kozutsumi.yml";
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum HookShell {
+    Zsh,
+    Bash,
+    Fish,
+}
+
+/// What the shell hook does with the parcels of a project
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HookAction {
+    /// Print how to open them
+    #[default]
+    Suggest,
+    /// Open them right away. Only use this if you trust the projects you enter
+    Open,
+}
+
+/// The snippet to add to the shell's config, running `suggest` whenever the directory changes
+pub fn snippet(shell: HookShell) -> anyhow::Result<String> {
+    let exe = env::current_exe()?;
+    let exe = format!("'{}'", exe.to_string_lossy().replace('\'', r"'\''"));
+    Ok(match shell {
+        HookShell::Zsh => format!(
+            r#"_kozutsumi_hook() {{ {exe} suggest; }}
+autoload -Uz add-zsh-hook
+add-zsh-hook chpwd _kozutsumi_hook
+"#
+        ),
+        HookShell::Bash => format!(
+            r#"_kozutsumi_hook() {{
+  if [ "$PWD" != "$_KOZUTSUMI_DIR" ]; then
+    _KOZUTSUMI_DIR="$PWD"
+    {exe} suggest
+  fi
+}}
+PROMPT_COMMAND="_kozutsumi_hook${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}"
+"#
+        ),
+        HookShell::Fish => format!(
+            r#"function _kozutsumi_hook --on-variable PWD
+    {exe} suggest
+end
+"#
+        ),
+    })
+}
+
+/// Suggest or open the parcels of the project in the current directory, following `settings.hook`
+pub fn suggest(config: &ParcelConfig, opener: &dyn Opener) -> anyhow::Result<()> {
+    let path = Path::new(PROJECT_CONFIG);
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let project = ParcelConfig::load(path)?;
+    let mut names = project.parcels.keys().collect::<Vec<_>>();
+    names.sort();
+    for name in names {
+        match config.settings.hook {
+            HookAction::Suggest => eprintln!(
+                "{}",
+                t!(
+                    "hook-suggestion",
+                    name = name,
+                    command = format!("kozutsumi --config {} open {}", PROJECT_CONFIG, name)
+                )
+            ),
+            HookAction::Open => ParcelCommands::open(&project, name, &OpenArgs::default(), opener)?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,85 @@
+//! Translation of user-facing messages, backed by [Fluent](https://projectfluent.org).
+//!
+//! Messages are looked up by id in the `.ftl` files under `locales/`, which are embedded at build time.
+//! Ids missing from a translation fall back to the English message.
+
+use std::{env, sync::OnceLock};
+
+use fluent_bundle::{FluentArgs, FluentResource, concurrent::FluentBundle};
+use unic_langid::LanguageIdentifier;
+
+/// Available locales, the first one being the fallback
+const LOCALES: &[(&str, &str)] = &[("en", include_str!("../locales/en.ftl"))];
+
+static BUNDLE: OnceLock<FluentBundle<FluentResource>> = OnceLock::new();
+
+/// Select the locale used for messages, from the `locale` setting or else `LANG`.
+///
+/// Has no effect once a message has been translated.
+pub fn init(locale: Option<&str>) {
+    let _ = BUNDLE.set(bundle(locale));
+}
+
+fn bundle(locale: Option<&str>) -> FluentBundle<FluentResource> {
+    let requested = locale
+        .map(str::to_string)
+        .or_else(|| env::var("LANG").ok())
+        .unwrap_or_default();
+    // `LANG` looks like `fr_FR.UTF-8`, only the language is matched
+    let language = requested.split(['_', '-', '.']).next().unwrap_or_default();
+
+    let (fallback, fallback_source) = LOCALES[0];
+    let (lang, source) = LOCALES
+        .iter()
+        .find(|(lang, _)| *lang == language)
+        .copied()
+        .unwrap_or(LOCALES[0]);
+
+    let langid = lang.parse::<LanguageIdentifier>().unwrap_or_default();
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // Placeables are wrapped in Unicode isolation marks by default, which terminals print literally
+    bundle.set_use_isolating(false);
+
+    // Messages that fail to parse are skipped, so they fall back to their id
+    let resource = |source: &str| {
+        FluentResource::try_new(source.to_string()).unwrap_or_else(|(resource, _)| resource)
+    };
+    bundle
+        .add_resource(resource(fallback_source))
+        .expect("the fallback locale has no duplicate messages");
+    if lang != fallback {
+        bundle.add_resource_overriding(resource(source));
+    }
+    bundle
+}
+
+/// The translation of the message `id`, or the id itself if it doesn't exist
+pub fn message(id: &str, args: Option<&FluentArgs>) -> String {
+    let bundle = BUNDLE.get_or_init(|| bundle(None));
+    let Some(pattern) = bundle.get_message(id).and_then(|message| message.value()) else {
+        return id.to_string();
+    };
+
+    let mut errors = Vec::new();
+    bundle
+        .format_pattern(pattern, args, &mut errors)
+        .into_owned()
+}
+
+/// Translate a message, with optional `name = value` arguments:
+///
+/// ```ignore
+/// t!("parcel-opened", name = "work")
+/// ```
+macro_rules! t {
+    ($id:literal) => {
+        $crate::i18n::message($id, None)
+    };
+    ($id:literal, $($key:ident = $value:expr),+ $(,)?) => {{
+        let mut args = fluent_bundle::FluentArgs::new();
+        $(args.set(stringify!($key), $value.to_string());)+
+        $crate::i18n::message($id, Some(&args))
+    }};
+}
+
+pub(crate) use t;
@@ -0,0 +1,234 @@
+//! Merging the parcels of another config file, such as a shared parcel pack, into the config.
+//!
+//! The imported parcels are appended to the config as a new document, which leaves what is already
+//! written untouched and overrides the earlier definitions of the parcels it overwrites.
+
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, IsTerminal},
+    path::Path,
+};
+
+use clap::ValueEnum;
+use serde_yaml::{Mapping, Value};
+
+use crate::{
+    backup,
+    config::{Parcel, ParcelConfig},
+    i18n::t,
+    utils,
+};
+
+/// What to do with an imported parcel named like a parcel of the config with different entries.
+///
+/// Imported parcels with the same entries as the config's are always skipped.
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum Collision {
+    /// Ask for each parcel, or skip them when not run in a terminal
+    #[default]
+    Ask,
+    /// Keep the parcel of the config
+    Skip,
+    /// Replace the parcel of the config with the imported one
+    Overwrite,
+    /// Import the parcel under a free name, like `dev-2`
+    Rename,
+    /// Add the imported entries the parcel of the config doesn't have to it
+    Merge,
+}
+
+/// Choices offered when asking, in order
+const CHOICES: [Collision; 4] = [
+    Collision::Skip,
+    Collision::Overwrite,
+    Collision::Merge,
+    Collision::Rename,
+];
+
+/// Import the parcels of the file at `other` into the config, their names prefixed with `prefix`.
+///
+/// The named entries of the file come along, except for the ones the config already defines,
+/// so that the imported parcels can still reference them. Its settings and handlers are left out.
+pub fn run(
+    config: &ParcelConfig,
+    config_path: &Path,
+    other: &Path,
+    prefix: &str,
+    collision: Collision,
+) -> anyhow::Result<()> {
+    if config_path.extension().is_some_and(|ext| ext == "toml") {
+        anyhow::bail!(t!("import-toml", path = config_path.display()));
+    }
+
+    let mut pack = ParcelConfig::load_mapping(other)?;
+    let mut own = ParcelConfig::load_mapping(config_path)?;
+    let interactive = io::stdin().is_terminal();
+    let mut document = Mapping::new();
+    if let Some(Value::Mapping(entries)) = pack.remove("entries") {
+        let missing = entries
+            .into_iter()
+            .filter(|(name, _)| {
+                name.as_str()
+                    .is_none_or(|name| !config.entries.contains_key(name))
+            })
+            .collect::<Mapping>();
+        if !missing.is_empty() {
+            document.insert("entries".into(), Value::Mapping(missing));
+        }
+    }
+    pack.remove("settings");
+    pack.remove("handlers");
+
+    let mut taken = config.parcels.keys().cloned().collect::<HashSet<_>>();
+    let mut imported = 0;
+    for (name, mut parcel) in pack {
+        let Some(name) = name.as_str() else {
+            continue;
+        };
+        let mut name = format!("{}{}", prefix, name);
+        if taken.contains(&name) {
+            if config
+                .parcels
+                .get(&name)
+                .is_some_and(|mine| same_entries(config, mine, &parcel))
+            {
+                println!("{}", t!("import-identical", name = name));
+                continue;
+            }
+            let collision = match collision {
+                Collision::Ask if interactive => ask(config, &name)?,
+                Collision::Ask => Collision::Skip,
+                collision => collision,
+            };
+            match collision {
+                Collision::Skip => {
+                    println!("{}", t!("import-skipped", name = name));
+                    continue;
+                }
+                Collision::Overwrite => println!("{}", t!("import-overwritten", name = name)),
+                Collision::Rename => {
+                    let renamed = (2..)
+                        .map(|n| format!("{}-{}", name, n))
+                        .find(|renamed| !taken.contains(renamed))
+                        .unwrap();
+                    println!("{}", t!("import-renamed", name = name, renamed = renamed));
+                    name = renamed;
+                }
+                Collision::Merge => {
+                    let Some(mine) = own.remove(name.as_str()) else {
+                        continue;
+                    };
+                    parcel = merge(mine, parcel);
+                    println!("{}", t!("import-merged", name = name));
+                }
+                Collision::Ask => unreachable!(),
+            }
+        } else {
+            println!("{}", t!("import-added", name = name));
+        }
+        taken.insert(name.clone());
+        document.insert(name.into(), parcel);
+        imported += 1;
+    }
+
+    if imported == 0 {
+        println!("{}", t!("import-nothing", path = other.display()));
+        return Ok(());
+    }
+
+    backup::before_mutation(config_path)?;
+    let mut contents = fs::read_to_string(config_path)?;
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    contents.push_str(&format!("---\n# Imported from {}\n", other.display()));
+    contents.push_str(&serde_yaml::to_string(&document)?);
+    utils::write_atomic(config_path, contents)?;
+    Ok(())
+}
+
+/// Whether the imported parcel opens the same entries as the parcel of the config
+fn same_entries(config: &ParcelConfig, mine: &Parcel, theirs: &Value) -> bool {
+    let Ok(mut theirs) = serde_yaml::from_value::<Parcel>(theirs.clone()) else {
+        return false;
+    };
+    for entry in theirs.entries_mut() {
+        config.resolve_entry(entry);
+    }
+    mine.entries()
+        .map(ToString::to_string)
+        .eq(theirs.entries().map(ToString::to_string))
+}
+
+/// The entries of a parcel as written, either the parcel itself or its `entries` key
+fn entries_of(parcel: &mut Value) -> Option<&mut Vec<Value>> {
+    match parcel {
+        Value::Sequence(entries) => Some(entries),
+        Value::Mapping(parcel) => match parcel
+            .entry("entries".into())
+            .or_insert_with(|| Value::Sequence(Vec::new()))
+        {
+            Value::Sequence(entries) => Some(entries),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The parcel of the config, with the imported entries it doesn't have added after its own
+fn merge(mut mine: Value, mut theirs: Value) -> Value {
+    let added = entries_of(&mut theirs)
+        .map(std::mem::take)
+        .unwrap_or_default();
+    if let Some(entries) = entries_of(&mut mine) {
+        for entry in added {
+            if !entries.contains(&entry) {
+                entries.push(entry);
+            }
+        }
+    }
+    mine
+}
+
+#[cfg(feature = "dialog")]
+fn ask(config: &ParcelConfig, name: &str) -> io::Result<Collision> {
+    let items = CHOICES.map(choice_label);
+    let i = dialoguer::Select::with_theme(&*utils::dialog_theme(&config.settings))
+        .with_prompt(t!("import-conflict", name = name))
+        .items(&items)
+        .default(0)
+        .interact()
+        .map_err(io::Error::other)?;
+    Ok(CHOICES[i])
+}
+
+/// Ask for the number of the choice, keeping the parcel of the config if nothing valid is typed
+#[cfg(not(feature = "dialog"))]
+fn ask(_config: &ParcelConfig, name: &str) -> io::Result<Collision> {
+    use std::io::{BufRead, Write};
+
+    eprintln!("{}", t!("import-conflict", name = name));
+    for (i, choice) in CHOICES.into_iter().enumerate() {
+        eprintln!("  {}) {}", i + 1, choice_label(choice));
+    }
+    eprint!("{} ", t!("import-choose"));
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(answer
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| CHOICES.get(n.wrapping_sub(1)).copied())
+        .unwrap_or(Collision::Skip))
+}
+
+fn choice_label(choice: Collision) -> String {
+    match choice {
+        Collision::Overwrite => t!("import-take-theirs"),
+        Collision::Merge => t!("import-merge-entries"),
+        Collision::Rename => t!("import-rename"),
+        Collision::Skip | Collision::Ask => t!("import-keep-mine"),
+    }
+}
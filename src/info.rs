@@ -0,0 +1,133 @@
+//! Everything about a single parcel in one view: what it is, what it opens, how it was used,
+//! and whether it can be opened as written.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+use crate::{
+    config::{Entry, ParcelConfig, duration},
+    history::{History, Stats},
+    i18n::t,
+    schedule, utils,
+};
+
+#[derive(Debug, Serialize)]
+pub struct Info<'a> {
+    pub name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<&'a str>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub tags: &'a [String],
+    /// Number of entries of each type
+    pub entries: BTreeMap<&'static str, usize>,
+    #[serde(flatten)]
+    pub stats: Stats,
+    /// What would keep the parcel from opening as written, empty when nothing would
+    pub problems: Vec<String>,
+}
+
+impl<'a> Info<'a> {
+    pub fn collect(config: &'a ParcelConfig, name: &str) -> anyhow::Result<Self> {
+        let (name, parcel) = utils::find_parcel(config, name)?;
+
+        let mut entries = BTreeMap::new();
+        for entry in parcel.entries() {
+            *entries.entry(entry.kind()).or_default() += 1;
+        }
+
+        let mut problems = parcel
+            .entries()
+            .filter_map(|entry| match entry {
+                Entry::File(path) if !path.exists() => {
+                    Some(t!("info-missing-path", path = path.display()))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if let Some(cron) = &parcel.schedule
+            && let Err(e) = schedule::intervals(cron)
+        {
+            problems.push(t!("info-invalid-schedule", reason = e));
+        }
+
+        Ok(Self {
+            name,
+            description: parcel.description.as_deref(),
+            tags: &parcel.tags,
+            entries,
+            stats: History::open()?.stats(name)?,
+            problems,
+        })
+    }
+}
+
+/// How long ago the time was, in its largest whole unit, e.g. `3d`
+fn ago(secs: u64) -> String {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH + Duration::from_secs(secs))
+        .unwrap_or_default()
+        .as_secs();
+    match elapsed {
+        86400.. => format!("{}d", elapsed / 86400),
+        3600.. => format!("{}h", elapsed / 3600),
+        60.. => format!("{}m", elapsed / 60),
+        _ => format!("{}s", elapsed),
+    }
+}
+
+impl Display for Info<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.name)?;
+        if let Some(description) = self.description {
+            writeln!(f, "  {}", description)?;
+        }
+        if !self.tags.is_empty() {
+            let tags = self.tags.iter().map(|tag| format!("#{}", tag));
+            writeln!(f, "  {}", tags.collect::<Vec<_>>().join(" "))?;
+        }
+
+        let total = self.entries.values().sum::<usize>();
+        let kinds = self
+            .entries
+            .iter()
+            .map(|(kind, count)| format!("{} {}", kind, count))
+            .collect::<Vec<_>>();
+        writeln!(
+            f,
+            "{}",
+            t!("info-entries", count = total, kinds = kinds.join(", "))
+        )?;
+
+        let stats = &self.stats;
+        match stats.last_opened {
+            Some(secs) => writeln!(f, "{}", t!("info-last-opened", ago = ago(secs)))?,
+            None => writeln!(f, "{}", t!("info-never-opened"))?,
+        }
+        if stats.opens > 0 {
+            writeln!(
+                f,
+                "{}",
+                t!("info-opens", count = stats.opens, failed = stats.failed)
+            )?;
+        }
+        if let Some(ms) = stats.average_ms {
+            let average = duration::format(Duration::from_millis(ms));
+            writeln!(f, "{}", t!("info-average", duration = average))?;
+        }
+
+        if self.problems.is_empty() {
+            writeln!(f, "{}", t!("info-valid"))?;
+        } else {
+            writeln!(f, "{}", t!("info-invalid"))?;
+            for problem in &self.problems {
+                writeln!(f, "  - {}", problem)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,28 @@
+//! Ctrl-C while a parcel is opened, which stops opening entries instead of killing the process mid-way.
+
+use std::sync::{
+    Once,
+    atomic::{AtomicBool, Ordering},
+};
+
+/// Exit code of a process stopped by Ctrl-C, as shells report it
+pub const EXIT_CODE: u8 = 130;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Catch Ctrl-C from now on. A second Ctrl-C exits right away, in case something doesn't stop
+pub fn install() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let _ = ctrlc::set_handler(|| {
+            if INTERRUPTED.swap(true, Ordering::SeqCst) {
+                std::process::exit(EXIT_CODE.into());
+            }
+        });
+    });
+}
+
+/// Whether Ctrl-C was pressed since [`install`]
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
@@ -0,0 +1,46 @@
+//! The library behind the `kozutsumi` command, for tools working with its config and parcels.
+//!
+//! Entries are parsed from their string form with [`config::Entry`]'s `FromStr`, the same way
+//! the config and the command line parse them.
+
+pub mod autostart;
+pub mod backup;
+pub mod calendar;
+pub mod cli;
+#[cfg(feature = "completions")]
+pub mod completions;
+#[cfg(feature = "rhai")]
+pub mod condition;
+pub mod config;
+pub mod doctor;
+pub mod dotenv;
+pub mod engine;
+pub mod error;
+pub mod graph;
+pub mod history;
+pub mod hook;
+pub mod i18n;
+pub mod import;
+pub mod info;
+pub mod interrupt;
+#[cfg(feature = "lua")]
+pub mod lua;
+pub mod opener;
+pub mod output;
+pub mod placeholders;
+pub mod plan;
+#[cfg(feature = "plugins")]
+pub mod plugins;
+#[cfg(feature = "dialog")]
+pub mod preview;
+pub mod processes;
+pub mod schedule;
+pub mod shortcuts;
+pub mod spotlight;
+pub mod state;
+pub mod uninstall;
+pub mod update;
+pub mod utils;
+
+#[cfg(not(target_os = "macos"))]
+compile_error!("This program is currently only supported on macOS.");
@@ -0,0 +1,37 @@
+//! Parcels whose entries are returned by a Lua script when they are opened.
+
+use anyhow::Context as _;
+use mlua::{Lua, LuaSerdeExt as _, Value};
+
+use crate::{
+    config::{ParcelConfig, Step},
+    i18n::t,
+};
+
+/// Run the script and return the entries it evaluates to, resolved against the config.
+///
+/// The script returns a list of entries, written as strings or as tables like in the config,
+/// including their options:
+///
+/// ```lua
+/// local projects = {}
+/// for dir in io.popen("ls -td ~/Projects/*/ | head -3"):lines() do
+///   table.insert(projects, { code = dir })
+/// end
+/// return projects
+/// ```
+pub fn entries(config: &ParcelConfig, name: &str, source: &str) -> anyhow::Result<Vec<Step>> {
+    let lua = Lua::new();
+    let value: Value = lua
+        .load(source)
+        .set_name(name)
+        .eval()
+        .with_context(|| t!("lua-failed", name = name))?;
+    let mut steps: Vec<Step> = lua
+        .from_value(value)
+        .with_context(|| t!("lua-failed", name = name))?;
+    for step in &mut steps {
+        config.resolve_entry(&mut step.entry);
+    }
+    Ok(steps)
+}
@@ -1,23 +1,22 @@
-#![feature(if_let_guard, string_remove_matches, str_as_str)]
-
-mod cli;
-mod config;
-mod utils;
-
 use std::process::ExitCode;
 
 use clap::Parser;
 
-use crate::cli::ParcelCLI;
-
-#[cfg(not(target_os = "macos"))]
-compile_error!("This program is currently only supported on macOS.");
+use kozutsumi::{cli::ParcelCLI, engine, interrupt};
 
 fn main() -> ExitCode {
+    // Answers the shell's completion requests when run by the scripts of `completions`
+    #[cfg(feature = "completions")]
+    clap_complete::CompleteEnv::with_factory(<ParcelCLI as clap::CommandFactory>::command)
+        .complete();
+
     let cli = ParcelCLI::parse();
 
     if let Err(e) = cli.run() {
-        eprintln!("{}", e);
+        cli.report(&e);
+        if e.is::<engine::Interrupted>() {
+            return ExitCode::from(interrupt::EXIT_CODE);
+        }
         return ExitCode::FAILURE;
     }
 
@@ -0,0 +1,568 @@
+//! Backends opening entries, so that what opening an entry does can be swapped out
+//! without touching the engine or the commands.
+
+use std::{
+    collections::BTreeMap,
+    io,
+    ops::Deref,
+    os::unix::process::CommandExt as _,
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Stdio},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use block2::RcBlock;
+use objc2_app_kit::{
+    NSApplicationActivationOptions, NSRunningApplication, NSWorkspace, NSWorkspaceOpenConfiguration,
+};
+use objc2_foundation::{NSError, NSString, NSURL};
+
+use crate::{config::Entry, i18n::t, plan, processes};
+
+/// How often entries are checked for completion when they have a deadline
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How opening an entry ended
+pub enum Outcome {
+    Finished,
+    /// The entry keeps running in the background, as the process with this id
+    Detached(u32),
+    /// The deadline was reached before the entry finished
+    Cancelled,
+    /// The command of the entry exited with an error
+    Failed {
+        status: ExitStatus,
+        stderr: String,
+    },
+}
+
+pub trait Opener: Sync {
+    /// Open the entry and wait for it to finish, unless it is detached.
+    /// An entry still running at the deadline is cancelled.
+    fn open(&self, entry: &Entry, deadline: Option<Instant>) -> io::Result<Outcome>;
+
+    /// Undo what opening the entry did, for the entries that can be closed
+    fn close(&self, entry: &Entry) -> io::Result<()>;
+
+    /// What opening the entry runs, as shown by `list --resolve`
+    fn describe(&self, entry: &Entry) -> String;
+
+    /// Whether the entry is open already: its app running, or its file open in a process.
+    /// Other entries never are
+    fn is_open(&self, entry: &Entry) -> bool;
+}
+
+/// Runs the commands of the entries
+pub struct System;
+
+impl Opener for System {
+    fn open(&self, entry: &Entry, deadline: Option<Instant>) -> io::Result<Outcome> {
+        if entry.detached() {
+            // In its own process group, so that stopping it also stops the processes it started
+            let child = entry
+                .command()
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .process_group(0)
+                .spawn()?;
+            return Ok(Outcome::Detached(child.id()));
+        }
+        #[cfg(feature = "shell")]
+        if let Entry::Pipeline { pipeline } = entry {
+            return run_pipeline(pipeline, deadline);
+        }
+        wait(entry.command(), deadline)
+    }
+
+    fn close(&self, entry: &Entry) -> io::Result<()> {
+        if let Some(mut command) = entry.close_command() {
+            command.output()?;
+        }
+        Ok(())
+    }
+
+    fn describe(&self, entry: &Entry) -> String {
+        #[cfg(feature = "shell")]
+        if let Entry::Pipeline { pipeline } = entry {
+            return pipeline
+                .iter()
+                .map(|cmd| plan::command_line(Command::new("sh").arg("-c").arg(cmd)))
+                .collect::<Vec<_>>()
+                .join(" | ");
+        }
+        plan::command_line(&entry.command())
+    }
+
+    fn is_open(&self, entry: &Entry) -> bool {
+        match entry {
+            Entry::App(app) => is_app_running(&app.name),
+            Entry::File(path) => processes::is_file_open(path),
+            _ => false,
+        }
+    }
+}
+
+/// Opens applications, files, and URLs through Launch Services directly, instead of running `open` for each of them,
+/// so that failures come back with their cause. The other entries are opened by [`System`]
+pub struct Workspace;
+
+impl Workspace {
+    /// Open the entry through `NSWorkspace`, or return `None` if it can't be
+    fn launch(entry: &Entry, deadline: Option<Instant>) -> Option<io::Result<Outcome>> {
+        let workspace = NSWorkspace::sharedWorkspace();
+        let opened = match entry {
+            // Environment variables can only be passed through `open --env`
+            Entry::App(app) if app.env.is_empty() => {
+                return Some(Self::launch_app(&workspace, &app.name, deadline));
+            }
+            Entry::File(path) => {
+                if !path.exists() {
+                    return Some(Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        t!("entry-file-missing", path = path.display()),
+                    )));
+                }
+                let url = NSURL::fileURLWithPath(&NSString::from_str(&path.to_string_lossy()));
+                workspace.openURL(&url)
+            }
+            Entry::Url(url) => {
+                let Some(url) = NSURL::URLWithString(&NSString::from_str(url.as_str())) else {
+                    return Some(Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        t!("entry-url-failed", url = url.as_str()),
+                    )));
+                };
+                workspace.openURL(&url)
+            }
+            _ => return None,
+        };
+
+        Some(if opened {
+            Ok(Outcome::Finished)
+        } else {
+            Err(io::Error::other(t!("entry-open-failed", entry = entry)))
+        })
+    }
+
+    /// Launch the application, waiting for Launch Services to say whether it did until the deadline
+    fn launch_app(
+        workspace: &NSWorkspace,
+        name: &str,
+        deadline: Option<Instant>,
+    ) -> io::Result<Outcome> {
+        let Some(url) = app_url(workspace, name) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                t!("entry-app-not-found", name = name),
+            ));
+        };
+        let (sender, receiver) = mpsc::channel();
+        let completed = RcBlock::new(move |_: *mut NSRunningApplication, error: *mut NSError| {
+            // SAFETY: Launch Services passes either null or an error that outlives the handler
+            let error =
+                unsafe { error.as_ref() }.map(|error| error.localizedDescription().to_string());
+            let _ = sender.send(error);
+        });
+        workspace.openApplicationAtURL_configuration_completionHandler(
+            &url,
+            &NSWorkspaceOpenConfiguration::configuration(),
+            Some(&completed),
+        );
+        let error = match deadline {
+            Some(deadline) => {
+                match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(error) => error,
+                    Err(mpsc::RecvTimeoutError::Timeout) => return Ok(Outcome::Cancelled),
+                    Err(mpsc::RecvTimeoutError::Disconnected) => None,
+                }
+            }
+            None => receiver.recv().unwrap_or_default(),
+        };
+        match error {
+            Some(error) => Err(io::Error::other(error)),
+            None => Ok(Outcome::Finished),
+        }
+    }
+}
+
+/// Where the application is, given as a path, a bundle identifier, or the name of an application in one of
+/// the applications folders
+fn app_url(workspace: &NSWorkspace, name: &str) -> Option<impl Deref<Target = NSURL>> {
+    let path = Path::new(name);
+    if path.is_absolute() {
+        return path
+            .exists()
+            .then(|| NSURL::fileURLWithPath(&NSString::from_str(name)));
+    }
+    if let Some(url) = workspace.URLForApplicationWithBundleIdentifier(&NSString::from_str(name)) {
+        return Some(url);
+    }
+    let bundle = match path.extension() {
+        Some(_) => PathBuf::from(path),
+        None => path.with_extension("app"),
+    };
+    let home = std::env::var_os("HOME").map(|home| Path::new(&home).join("Applications"));
+    [
+        Path::new("/Applications"),
+        Path::new("/Applications/Utilities"),
+        Path::new("/System/Applications"),
+        Path::new("/System/Applications/Utilities"),
+    ]
+    .into_iter()
+    .map(Path::to_path_buf)
+    .chain(home)
+    .map(|folder| folder.join(&bundle))
+    .find(|path| path.exists())
+    .map(|path| NSURL::fileURLWithPath(&NSString::from_str(&path.to_string_lossy())))
+}
+
+/// The running application with this name, or at this path, if any
+fn running_app(name: &str) -> Option<impl Deref<Target = NSRunningApplication>> {
+    let name = Path::new(name)
+        .file_stem()
+        .map_or(name.into(), |stem| stem.to_string_lossy());
+    let workspace = NSWorkspace::sharedWorkspace();
+    workspace.runningApplications().iter().find(|app| {
+        app.localizedName()
+            .is_some_and(|app| app.to_string() == name)
+    })
+}
+
+/// Whether an application with this name, or at this path, is running
+pub fn is_app_running(name: &str) -> bool {
+    running_app(name).is_some()
+}
+
+/// Bring the windows of the application forward if it is running, returning whether it was
+pub fn raise_app(name: &str) -> bool {
+    running_app(name).is_some_and(|app| {
+        app.activateWithOptions(NSApplicationActivationOptions::ActivateAllWindows)
+    })
+}
+
+/// Path of the application Launch Services opens a file or URL entry with, if it has one
+pub fn default_app(entry: &Entry) -> Option<String> {
+    let url = match entry {
+        Entry::File(path) => NSURL::fileURLWithPath(&NSString::from_str(&path.to_string_lossy())),
+        Entry::Url(url) => NSURL::URLWithString(&NSString::from_str(url.as_str()))?,
+        _ => return None,
+    };
+    let workspace = NSWorkspace::sharedWorkspace();
+    let app = workspace.URLForApplicationToOpenURL(&url)?;
+    app.path().map(|path| path.to_string())
+}
+
+impl Opener for Workspace {
+    fn open(&self, entry: &Entry, deadline: Option<Instant>) -> io::Result<Outcome> {
+        Self::launch(entry, deadline).unwrap_or_else(|| System.open(entry, deadline))
+    }
+
+    fn close(&self, entry: &Entry) -> io::Result<()> {
+        System.close(entry)
+    }
+
+    fn describe(&self, entry: &Entry) -> String {
+        match entry {
+            Entry::App(app) if app.env.is_empty() => {
+                format!("NSWorkspace openApplicationAtURL {:?}", app.name)
+            }
+            Entry::File(path) => format!("NSWorkspace openURL {:?}", path.display().to_string()),
+            Entry::Url(url) => format!("NSWorkspace openURL {:?}", url.as_str()),
+            _ => System.describe(entry),
+        }
+    }
+
+    fn is_open(&self, entry: &Entry) -> bool {
+        System.is_open(entry)
+    }
+}
+
+/// Run the command until it finishes, killing it if it is still running at the deadline
+pub fn wait(mut command: Command, deadline: Option<Instant>) -> io::Result<Outcome> {
+    let Some(deadline) = deadline else {
+        return command.output().map(|output| {
+            finished(
+                output.status,
+                String::from_utf8_lossy(&output.stderr).into_owned(),
+            )
+        });
+    };
+
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(finished(status, String::new()));
+        }
+        if Instant::now() >= deadline {
+            child.kill()?;
+            child.wait()?;
+            return Ok(Outcome::Cancelled);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Run the commands with the stdout of each piped into the stdin of the next, and wait for all of them.
+/// The pipeline fails with the first command that failed
+#[cfg(feature = "shell")]
+fn run_pipeline(commands: &[String], deadline: Option<Instant>) -> io::Result<Outcome> {
+    use std::{io::Read as _, mem, process::Child};
+
+    let mut children: Vec<Child> = Vec::with_capacity(commands.len());
+    let mut input = Stdio::null();
+    for (i, cmd) in commands.iter().enumerate() {
+        let output = if i + 1 < commands.len() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        };
+        let spawned = Command::new("sh")
+            .arg("-c")
+            .arg(cmd)
+            .envs(crate::dotenv::vars())
+            .stdin(mem::replace(&mut input, Stdio::null()))
+            .stdout(output)
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(e) => {
+                for mut child in children {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                }
+                return Err(e);
+            }
+        };
+        if let Some(stdout) = child.stdout.take() {
+            input = Stdio::from(stdout);
+        }
+        children.push(child);
+    }
+
+    // Read stderr as the commands run, so that none of them blocks on a full pipe
+    let stderrs = children
+        .iter_mut()
+        .map(|child| {
+            let mut stderr = child.stderr.take();
+            thread::spawn(move || {
+                let mut text = String::new();
+                if let Some(stderr) = &mut stderr {
+                    let _ = stderr.read_to_string(&mut text);
+                }
+                text
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut statuses = vec![None; children.len()];
+    while statuses.iter().any(Option::is_none) {
+        for (child, status) in children.iter_mut().zip(&mut statuses) {
+            if status.is_none() {
+                *status = child.try_wait()?;
+            }
+        }
+        if statuses.iter().any(Option::is_none)
+            && deadline.is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            for child in &mut children {
+                let _ = child.kill();
+                child.wait()?;
+            }
+            return Ok(Outcome::Cancelled);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    let stderrs = stderrs
+        .into_iter()
+        .map(|reader| reader.join().unwrap_or_default());
+    for (status, stderr) in statuses.into_iter().flatten().zip(stderrs) {
+        if !status.success() {
+            return Ok(Outcome::Failed { status, stderr });
+        }
+    }
+    Ok(Outcome::Finished)
+}
+
+fn finished(status: ExitStatus, stderr: String) -> Outcome {
+    if status.success() {
+        Outcome::Finished
+    } else {
+        Outcome::Failed { status, stderr }
+    }
+}
+
+/// Opens the entries starting with the prefix of a user-defined handler through its command,
+/// and every other entry through `fallback`
+pub struct Handlers<'a> {
+    handlers: &'a BTreeMap<String, String>,
+    fallback: &'a dyn Opener,
+}
+
+impl<'a> Handlers<'a> {
+    pub fn new(handlers: &'a BTreeMap<String, String>, fallback: &'a dyn Opener) -> Self {
+        Self { handlers, fallback }
+    }
+
+    /// The command of the handler with the longest prefix matching the entry, if any.
+    /// `{uri}` in the command is replaced by the entry, passed as a separate argument so it needs no quoting,
+    /// though it can be quoted all the same
+    fn command(&self, entry: &Entry) -> Option<Command> {
+        let uri = entry.to_string();
+        let (_, template) = self
+            .handlers
+            .iter()
+            .filter(|(prefix, _)| uri.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())?;
+
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(uri_argument(template))
+            .arg("sh")
+            .arg(uri);
+        Some(command)
+    }
+}
+
+/// The command of a handler with each `{uri}` replaced by its first argument,
+/// quoted as the quotes and command substitutions around it need
+fn uri_argument(template: &str) -> String {
+    let mut command = String::new();
+    // Quotes and parentheses opened, innermost last
+    let mut open = Vec::new();
+    let mut rest = template;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("{uri}") {
+            command.push_str(match open.last() {
+                Some('\'') => "'\"$1\"'",
+                Some('"') => "$1",
+                _ => "\"$1\"",
+            });
+            rest = after;
+            continue;
+        }
+        let mut len = c.len_utf8();
+        match (open.last(), c) {
+            (Some('\''), '\'') | (Some('"'), '"') | (Some('('), ')') => {
+                open.pop();
+            }
+            (Some('\''), _) => {}
+            (_, '\\') => len += rest[len..].chars().next().map_or(0, char::len_utf8),
+            (_, '(') if open.last() != Some(&'"') || command.ends_with('$') => open.push('('),
+            (Some('"'), _) => {}
+            (_, '\'' | '"') => open.push(c),
+            _ => {}
+        }
+        command.push_str(&rest[..len]);
+        rest = &rest[len..];
+    }
+    command
+}
+
+impl Opener for Handlers<'_> {
+    fn open(&self, entry: &Entry, deadline: Option<Instant>) -> io::Result<Outcome> {
+        match self.command(entry) {
+            Some(command) => wait(command, deadline),
+            None => self.fallback.open(entry, deadline),
+        }
+    }
+
+    fn close(&self, entry: &Entry) -> io::Result<()> {
+        self.fallback.close(entry)
+    }
+
+    fn describe(&self, entry: &Entry) -> String {
+        match self.command(entry) {
+            Some(command) => plan::command_line(&command),
+            None => self.fallback.describe(entry),
+        }
+    }
+
+    fn is_open(&self, entry: &Entry) -> bool {
+        self.fallback.is_open(entry)
+    }
+}
+
+/// Records the entries it is given instead of opening them, to check what would be opened
+#[cfg(test)]
+#[derive(Default)]
+pub struct Recorder {
+    pub opened: std::sync::Mutex<Vec<Entry>>,
+    pub closed: std::sync::Mutex<Vec<Entry>>,
+}
+
+#[cfg(test)]
+impl Opener for Recorder {
+    fn open(&self, entry: &Entry, _deadline: Option<Instant>) -> io::Result<Outcome> {
+        self.opened.lock().unwrap().push(entry.clone());
+        Ok(Outcome::Finished)
+    }
+
+    fn close(&self, entry: &Entry) -> io::Result<()> {
+        self.closed.lock().unwrap().push(entry.clone());
+        Ok(())
+    }
+
+    fn describe(&self, entry: &Entry) -> String {
+        entry.to_string()
+    }
+
+    /// The entries it opened are open
+    fn is_open(&self, entry: &Entry) -> bool {
+        self.opened.lock().unwrap().contains(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handlers_describe_the_command_of_their_handler() {
+        let handlers = BTreeMap::from([("obsidian://".to_string(), "open -g {uri}".to_string())]);
+        let recorder = Recorder::default();
+        let opener = Handlers::new(&handlers, &recorder);
+
+        let note: Entry = "obsidian://open?vault=notes".parse().unwrap();
+        assert_eq!(
+            opener.describe(&note),
+            r#"sh -c "open -g \"$1\"" sh obsidian://open?vault=notes"#
+        );
+        let app: Entry = "Zed".parse().unwrap();
+        assert_eq!(opener.describe(&app), "Zed");
+    }
+
+    #[test]
+    fn handlers_pass_the_entry_whole_however_it_is_quoted() {
+        let entry: Entry = "/tmp/Notes & Ideas/Q3 plan.md".parse().unwrap();
+        for template in [
+            "printf %s {uri}",
+            "printf %s '{uri}'",
+            "printf %s \"{uri}\"",
+            "printf %s \"$(printf %s {uri})\"",
+        ] {
+            let handlers = BTreeMap::from([("/tmp/".to_string(), template.to_string())]);
+            let recorder = Recorder::default();
+            let output = Handlers::new(&handlers, &recorder)
+                .command(&entry)
+                .unwrap()
+                .output()
+                .unwrap();
+            assert_eq!(
+                String::from_utf8_lossy(&output.stdout),
+                "/tmp/Notes & Ideas/Q3 plan.md",
+                "{}",
+                template
+            );
+        }
+    }
+}
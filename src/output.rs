@@ -0,0 +1,275 @@
+use std::{
+    collections::HashMap,
+    env,
+    fmt::Write as _,
+    io::{self, IsTerminal},
+};
+
+use crate::{
+    cli::ColorChoice,
+    config::{Entry, Icons, Parcel, Settings, Step, Style},
+    opener::Opener,
+};
+
+/// Renders parcels for `list` and the fzf preview, following the output settings
+pub struct Printer<'a> {
+    settings: &'a Settings,
+    color: bool,
+    /// Show what opening each entry runs under it, as this opener would open it
+    resolve: Option<&'a dyn Opener>,
+}
+
+impl<'a> Printer<'a> {
+    pub fn new(settings: &'a Settings) -> Self {
+        Self::with_color(settings, use_color(settings, &io::stdout()))
+    }
+
+    /// A printer that uses colors or not regardless of where its output goes
+    pub fn with_color(settings: &'a Settings, color: bool) -> Self {
+        Self {
+            settings,
+            color,
+            resolve: None,
+        }
+    }
+
+    /// A printer that shows what opening each entry through the opener runs under it
+    pub fn resolving(self, resolve: Option<&'a dyn Opener>) -> Self {
+        Self { resolve, ..self }
+    }
+
+    pub fn entry(&self, entry: &Entry) -> String {
+        let Some(opener) = self.resolve else {
+            return format!("- {}", self.styled(entry));
+        };
+        let line = paint(
+            &Style("dim".to_string()),
+            &format!("$ {}", opener.describe(entry)),
+            self.color,
+        );
+        format!("- {}\n  {}", self.styled(entry), line)
+    }
+
+    /// The entry with its glyph, painted in the style of its type
+    fn styled(&self, entry: &Entry) -> String {
+        let theme = &self.settings.theme;
+        let style = match entry {
+            Entry::App(_) | Entry::Play(_) | Entry::Focus(_) => &theme.app,
+            Entry::File(_)
+            | Entry::TermDir { .. }
+            | Entry::Code { .. }
+            | Entry::Recent { .. }
+            | Entry::Spotlight(_)
+            | Entry::QuickLook(_) => &theme.file,
+            Entry::Compose(_)
+            | Entry::Launchd(_)
+            | Entry::KeyboardMaestro(_)
+            | Entry::BetterTouchTool(_) => &theme.shell,
+            Entry::Url(_) | Entry::NextMeeting => &theme.url,
+            #[cfg(feature = "shell")]
+            Entry::Shell { .. } | Entry::Pipeline { .. } => &theme.shell,
+        };
+        let text = match self.settings.icons.glyph(entry) {
+            Some(glyph) => format!("{} {}", glyph, entry),
+            None => entry.to_string(),
+        };
+        paint(style, &text, self.color)
+    }
+
+    pub fn parcel(&self, parcel: &Parcel) -> String {
+        let mut out = String::new();
+        for step in &parcel.entries {
+            let _ = writeln!(out, "{}", self.entry(&step.entry));
+        }
+        let groups = parcel
+            .phases
+            .iter()
+            .map(|phase| (phase.name.as_str(), phase.entries.as_slice()))
+            .chain((!parcel.then.is_empty()).then_some(("then", parcel.then.as_slice())));
+        for (group, steps) in groups {
+            let header = format!("[{}]", group);
+            let _ = writeln!(
+                out,
+                "{}",
+                paint(&self.settings.theme.phase, &header, self.color)
+            );
+            for step in steps {
+                let _ = writeln!(out, "{}", self.entry(&step.entry));
+            }
+        }
+        out
+    }
+
+    pub fn parcels(&self, parcels: &HashMap<String, Parcel>) -> String {
+        let mut out = String::new();
+        for (name, parcel) in parcels {
+            let header = format!("{}:", name);
+            let _ = writeln!(
+                out,
+                "{}",
+                paint(&self.settings.theme.parcel, &header, self.color)
+            );
+            out.push_str(&self.parcel(parcel));
+        }
+        out
+    }
+
+    /// Every parcel as a tree of its phases and entries, along with how many entries each contains
+    pub fn tree(&self, parcels: &HashMap<String, Parcel>) -> String {
+        enum Node<'a> {
+            Entry(&'a Entry),
+            /// A phase, or the `then` entries
+            Group(&'a str, &'a [Step]),
+        }
+
+        let theme = &self.settings.theme;
+        let mut names = parcels.keys().collect::<Vec<_>>();
+        names.sort();
+
+        let mut out = String::new();
+        for name in names {
+            let parcel = &parcels[name];
+            let header = format!("{} ({})", name, parcel.entries().count());
+            let _ = writeln!(out, "{}", paint(&theme.parcel, &header, self.color));
+
+            let nodes = parcel
+                .entries
+                .iter()
+                .map(|step| Node::Entry(&step.entry))
+                .chain(
+                    parcel
+                        .phases
+                        .iter()
+                        .map(|phase| Node::Group(&phase.name, &phase.entries)),
+                )
+                .chain((!parcel.then.is_empty()).then(|| Node::Group("then", &parcel.then)))
+                .collect::<Vec<_>>();
+            for (i, node) in nodes.iter().enumerate() {
+                let (branch, indent) = if i + 1 == nodes.len() {
+                    ("└──", "    ")
+                } else {
+                    ("├──", "│   ")
+                };
+                match node {
+                    Node::Entry(entry) => {
+                        let _ = writeln!(out, "{} {}", branch, self.styled(entry));
+                    }
+                    Node::Group(group, steps) => {
+                        let header = format!("[{}] ({})", group, steps.len());
+                        let _ = writeln!(
+                            out,
+                            "{} {}",
+                            branch,
+                            paint(&theme.phase, &header, self.color)
+                        );
+                        for (j, step) in steps.iter().enumerate() {
+                            let branch = if j + 1 == steps.len() {
+                                "└──"
+                            } else {
+                                "├──"
+                            };
+                            let _ =
+                                writeln!(out, "{}{} {}", indent, branch, self.styled(&step.entry));
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Whether colors should be written to the given stream.
+///
+/// In `auto` mode, colors are only used on terminals, and never when `NO_COLOR` is set.
+pub fn use_color(settings: &Settings, stream: &impl IsTerminal) -> bool {
+    match settings.colors {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && stream.is_terminal()
+        }
+    }
+}
+
+/// Wrap the text in the ANSI escape codes of the style
+pub fn paint(style: &Style, text: &str, color: bool) -> String {
+    let codes = style.ansi_codes();
+    if !color || codes.is_empty() {
+        return text.to_string();
+    }
+    format!("\x1b[{}m{}\x1b[0m", codes, text)
+}
+
+impl Style {
+    /// SGR parameters of the style, e.g. `1;36` for `cyan.bold`. Unknown parts are ignored
+    fn ansi_codes(&self) -> String {
+        self.0
+            .split('.')
+            .filter_map(|part| {
+                let code = match part.trim() {
+                    "bold" => "1",
+                    "dim" => "2",
+                    "italic" => "3",
+                    "underlined" => "4",
+                    "black" => "30",
+                    "red" => "31",
+                    "green" => "32",
+                    "yellow" => "33",
+                    "blue" => "34",
+                    "magenta" => "35",
+                    "cyan" => "36",
+                    "white" => "37",
+                    other => return other.parse::<u8>().ok().map(|n| format!("38;5;{}", n)),
+                };
+                Some(code.to_string())
+            })
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+}
+
+impl Icons {
+    /// The glyph for the entry's type, if glyphs are enabled
+    pub fn glyph(self, entry: &Entry) -> Option<&'static str> {
+        let glyph = match (self, entry) {
+            (Self::None, _) => return None,
+            (Self::Ascii, Entry::App(_)) => "[a]",
+            (Self::Ascii, Entry::File(_)) => "[f]",
+            (Self::Ascii, Entry::Url(_)) => "[u]",
+            #[cfg(feature = "shell")]
+            (Self::Ascii, Entry::Shell { .. }) => "[$]",
+            #[cfg(feature = "shell")]
+            (Self::Ascii, Entry::Pipeline { .. }) => "[|]",
+            (Self::Ascii, Entry::TermDir { .. }) => "[>]",
+            (Self::Ascii, Entry::Code { .. }) => "[c]",
+            (Self::Ascii, Entry::Compose(_)) => "[d]",
+            (Self::Ascii, Entry::Launchd(_)) => "[l]",
+            (Self::Ascii, Entry::Play(_)) => "[p]",
+            (Self::Ascii, Entry::Focus(_)) => "[z]",
+            (Self::Ascii, Entry::KeyboardMaestro(_) | Entry::BetterTouchTool(_)) => "[m]",
+            (Self::Ascii, Entry::NextMeeting) => "[v]",
+            (Self::Ascii, Entry::Recent { .. }) => "[r]",
+            (Self::Ascii, Entry::Spotlight(_)) => "[s]",
+            (Self::Ascii, Entry::QuickLook(_)) => "[q]",
+            (Self::Nerd, Entry::App(_)) => "\u{f135}",
+            (Self::Nerd, Entry::File(path)) if path.is_dir() => "\u{f07b}",
+            (Self::Nerd, Entry::File(_)) => "\u{f15b}",
+            (Self::Nerd, Entry::Url(_)) => "\u{f0ac}",
+            #[cfg(feature = "shell")]
+            (Self::Nerd, Entry::Shell { .. } | Entry::Pipeline { .. }) => "\u{f489}",
+            (Self::Nerd, Entry::TermDir { .. }) => "\u{f120}",
+            (Self::Nerd, Entry::Code { .. }) => "\u{f121}",
+            (Self::Nerd, Entry::Compose(_)) => "\u{f308}",
+            (Self::Nerd, Entry::Launchd(_)) => "\u{f013}",
+            (Self::Nerd, Entry::Play(_)) => "\u{f001}",
+            (Self::Nerd, Entry::Focus(_)) => "\u{f186}",
+            (Self::Nerd, Entry::KeyboardMaestro(_) | Entry::BetterTouchTool(_)) => "\u{f0e7}",
+            (Self::Nerd, Entry::NextMeeting) => "\u{f03d}",
+            (Self::Nerd, Entry::Recent { .. }) => "\u{f017}",
+            (Self::Nerd, Entry::Spotlight(_)) => "\u{f002}",
+            (Self::Nerd, Entry::QuickLook(_)) => "\u{f06e}",
+        };
+        Some(glyph)
+    }
+}
@@ -0,0 +1,282 @@
+//! `{{input:<prompt>}}` placeholders in entries, replaced by what is typed when the parcel is opened.
+//!
+//! `{{password:<prompt>}}` placeholders are typed without being shown, and are redacted
+//! from anything printed about the entries they end up in.
+
+use std::{collections::HashMap, io, sync::Mutex};
+
+use url::{Url, form_urlencoded};
+
+use crate::config::{Parcel, ParcelConfig};
+#[cfg(feature = "dialog")]
+use crate::utils;
+
+/// Braces are percent-encoded in the path of URLs, so placeholders are looked for in both forms
+const DELIMITERS: [(&str, &str); 2] = [("{{", "}}"), ("%7B%7B", "%7D%7D")];
+
+/// What is shown instead of passwords
+const REDACTED: &str = "••••••";
+
+/// Passwords typed so far, along with the forms URLs encode them in, to redact them from output
+static SECRETS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Kind {
+    Input,
+    Password,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Input => "input",
+            Self::Password => "password",
+        }
+    }
+}
+
+/// The text with every password typed for a placeholder replaced by a mask
+pub fn redact(text: &str) -> String {
+    let mut secrets = SECRETS.lock().unwrap().clone();
+    // Longer forms first, so that a form containing another is masked whole
+    secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+    secrets
+        .iter()
+        .filter(|secret| !secret.is_empty())
+        .fold(text.to_string(), |text, secret| {
+            text.replace(secret.as_str(), REDACTED)
+        })
+}
+
+/// Remember a password to redact, in every form it can take once filled into a URL entry,
+/// which percent-encodes it differently in each of its parts
+fn keep_secret(secret: &str) {
+    let mut url = Url::parse("https://example.com").unwrap();
+    url.set_path(secret);
+    let path = url.path().trim_start_matches('/').to_string();
+    url.set_query(Some(secret));
+    let query = url.query().unwrap_or_default().to_string();
+    url.set_fragment(Some(secret));
+    let fragment = url.fragment().unwrap_or_default().to_string();
+    let _ = url.set_password(Some(secret));
+    let password = url.password().unwrap_or_default().to_string();
+    let form = form_urlencoded::byte_serialize(secret.as_bytes()).collect::<String>();
+
+    let mut secrets = SECRETS.lock().unwrap();
+    for form in [secret.to_string(), path, query, fragment, password, form] {
+        if !secrets.contains(&form) {
+            secrets.push(form);
+        }
+    }
+}
+
+/// Fill in the placeholders of the parcel's entries.
+///
+/// Each distinct prompt is only asked once, even if it appears in several entries
+pub fn fill(config: &ParcelConfig, parcel: &mut Parcel) -> anyhow::Result<()> {
+    fill_with(config, parcel, |kind, prompt| match kind {
+        Kind::Input => ask(config, prompt),
+        Kind::Password => ask_password(config, prompt),
+    })
+}
+
+/// Fill in the placeholders with the answers of `answer`, within the texts of each entry
+/// so that entries keep their type and options
+fn fill_with(
+    config: &ParcelConfig,
+    parcel: &mut Parcel,
+    mut answer: impl FnMut(Kind, &str) -> io::Result<String>,
+) -> anyhow::Result<()> {
+    let mut answers = HashMap::new();
+    for entry in parcel.entries_mut() {
+        let mut changed = false;
+        entry.edit_texts(|text| {
+            // Answers aren't searched for placeholders, in case they look like one
+            let mut from = 0;
+            while let Some((start, end, kind, prompt)) = next_placeholder(text, from) {
+                let key = (kind, prompt);
+                let filled = match answers.get(&key) {
+                    Some(filled) => filled,
+                    None => {
+                        let filled = answer(kind, &key.1)?;
+                        if kind == Kind::Password {
+                            keep_secret(&filled);
+                        }
+                        answers.entry(key).or_insert(filled)
+                    }
+                };
+                text.replace_range(start..end, filled);
+                from = start + filled.len();
+                changed = true;
+            }
+            Ok(())
+        })?;
+        if changed {
+            config.resolve_entry(entry);
+        }
+    }
+    Ok(())
+}
+
+/// Byte range, kind, and prompt of the first placeholder of the text starting at or after `from`
+fn next_placeholder(text: &str, from: usize) -> Option<(usize, usize, Kind, String)> {
+    let text = &text[from..];
+    DELIMITERS
+        .iter()
+        .flat_map(|delimiters| [Kind::Input, Kind::Password].map(|kind| (delimiters, kind)))
+        .filter_map(|((open, close), kind)| {
+            let start = text.find(&format!("{}{}:", open, kind.name()))?;
+            let prompt_start = start + open.len() + kind.name().len() + 1;
+            let len = text[prompt_start..].find(close)?;
+            let prompt = text[prompt_start..prompt_start + len].replace("%20", " ");
+            Some((
+                from + start,
+                from + prompt_start + len + close.len(),
+                kind,
+                prompt,
+            ))
+        })
+        .min_by_key(|(start, ..)| *start)
+}
+
+#[cfg(feature = "dialog")]
+fn ask(config: &ParcelConfig, prompt: &str) -> io::Result<String> {
+    dialoguer::Input::with_theme(&*utils::dialog_theme(&config.settings))
+        .with_prompt(prompt)
+        .interact_text()
+        .map_err(io::Error::other)
+}
+
+#[cfg(not(feature = "dialog"))]
+fn ask(_config: &ParcelConfig, prompt: &str) -> io::Result<String> {
+    use std::io::{BufRead, Write};
+
+    eprint!("{}: ", prompt);
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
+#[cfg(feature = "dialog")]
+fn ask_password(config: &ParcelConfig, prompt: &str) -> io::Result<String> {
+    dialoguer::Password::with_theme(&*utils::dialog_theme(&config.settings))
+        .with_prompt(prompt)
+        .interact()
+        .map_err(io::Error::other)
+}
+
+/// Ask without echoing what is typed, by turning off the terminal's echo while reading
+#[cfg(not(feature = "dialog"))]
+fn ask_password(config: &ParcelConfig, prompt: &str) -> io::Result<String> {
+    use std::process::Command;
+
+    Command::new("stty").arg("-echo").status()?;
+    let answer = ask(config, prompt);
+    Command::new("stty").arg("echo").status()?;
+    eprintln!();
+    answer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Entry;
+
+    fn parcel(entries: &[&str]) -> Parcel {
+        Parcel {
+            entries: entries
+                .iter()
+                .map(|entry| entry.parse::<Entry>().unwrap().into())
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[cfg(feature = "shell")]
+    #[test]
+    fn filled_shell_entry_stays_a_shell_entry() {
+        let mut parcel = parcel(&["sh:echo {{password:VPN passphrase}} | vpn connect --stdin"]);
+        fill_with(&ParcelConfig::default(), &mut parcel, |_, _| {
+            Ok("hunter2".to_string())
+        })
+        .unwrap();
+
+        let Some(Entry::Shell { command, detach }) = parcel.entries().next() else {
+            panic!("expected a shell entry, got {:?}", parcel.entries().next());
+        };
+        assert_eq!(command, "echo hunter2 | vpn connect --stdin");
+        assert!(!detach);
+        assert_eq!(redact(command), "echo •••••• | vpn connect --stdin");
+    }
+
+    #[test]
+    fn filling_keeps_the_options_of_entries() {
+        let mut parcel = parcel(&["termdir:~/{{input:Project}}"]);
+        if let Some(Entry::TermDir { terminal, .. }) = parcel.entries_mut().next() {
+            *terminal = Some("iTerm".to_string());
+        }
+        fill_with(&ParcelConfig::default(), &mut parcel, |_, _| {
+            Ok("acme".to_string())
+        })
+        .unwrap();
+
+        let Some(Entry::TermDir { dir, terminal }) = parcel.entries().next() else {
+            panic!(
+                "expected a termdir entry, got {:?}",
+                parcel.entries().next()
+            );
+        };
+        assert!(dir.ends_with("acme"));
+        assert_eq!(terminal.as_deref(), Some("iTerm"));
+    }
+
+    #[test]
+    fn each_prompt_is_asked_once() {
+        let mut parcel = parcel(&[
+            "code:~/{{input:Project}}",
+            "ql:~/{{input:Project}}/README.md",
+        ]);
+        let mut asked = 0;
+        fill_with(&ParcelConfig::default(), &mut parcel, |_, _| {
+            asked += 1;
+            Ok("acme".to_string())
+        })
+        .unwrap();
+        assert_eq!(asked, 1);
+    }
+
+    #[test]
+    fn answers_are_not_filled_in_again() {
+        let mut parcel = parcel(&["code:~/{{input:Project}}/{{input:Branch}}"]);
+        let mut asked = Vec::new();
+        fill_with(&ParcelConfig::default(), &mut parcel, |_, prompt| {
+            asked.push(prompt.to_string());
+            Ok(format!("{{{{input:{}}}}}", prompt))
+        })
+        .unwrap();
+
+        assert_eq!(asked, ["Project", "Branch"]);
+        let Some(Entry::Code { path, .. }) = parcel.entries().next() else {
+            panic!("expected a code entry, got {:?}", parcel.entries().next());
+        };
+        assert!(path.ends_with("{{input:Project}}/{{input:Branch}}"));
+    }
+
+    #[test]
+    fn passwords_encoded_in_urls_are_redacted() {
+        let mut parcel = parcel(&[
+            "https://example.com/{{password:Token}}/files?token={{password:Token}}#{{password:Token}}",
+        ]);
+        fill_with(&ParcelConfig::default(), &mut parcel, |_, _| {
+            Ok("p@ss word/!".to_string())
+        })
+        .unwrap();
+
+        let entry = parcel.entries().next().unwrap().to_string();
+        assert!(entry.contains("p@ss%20word/!"), "{}", entry);
+        let redacted = redact(&entry);
+        assert!(!redacted.contains("p@ss"), "{}", redacted);
+        assert!(!redacted.contains("word"), "{}", redacted);
+    }
+}
@@ -0,0 +1,200 @@
+use std::{fmt::Display, num::NonZeroUsize, process::Command, thread, time::Duration};
+
+use serde::{Serialize, Serializer};
+
+use crate::{
+    config::{Entry, MissingPath, Parcel, Settings, Step, duration},
+    i18n::t,
+};
+
+/// The resolved steps opening a parcel goes through, in order.
+///
+/// Both `open --plan` and the engine work off the same plan,
+/// so what gets printed is exactly what would be executed.
+#[derive(Debug, Serialize)]
+pub struct Plan<'a> {
+    pub parcel: &'a str,
+    pub stages: Vec<Stage<'a>>,
+    /// Time after which the entries still opening are cancelled
+    #[serde(serialize_with = "duration::serialize")]
+    pub timeout: Option<Duration>,
+    /// Times a failed entry is opened again, unless the entry sets its own
+    pub retries: u32,
+    /// Skip the entries that are already open
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub idempotent: bool,
+    /// Skip the entries opened by the last open of the parcel, which was interrupted or failed
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub resume: bool,
+    /// What to do with the file entries whose path doesn't exist, unless the entry says otherwise
+    pub missing_path: MissingPath,
+}
+
+/// A group of entries opened together, with at most `limit` of them in flight at once
+#[derive(Debug, Serialize)]
+pub struct Stage<'a> {
+    /// Name of the phase this stage comes from, if any
+    pub name: Option<&'a str>,
+    pub limit: usize,
+    /// Index of the stage that has to finish before this one starts
+    pub depends_on: Option<usize>,
+    /// Only open this stage if every entry of the previous stages opened successfully
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub on_success: bool,
+    #[serde(rename = "actions", serialize_with = "serialize_actions")]
+    pub entries: Vec<&'a Step>,
+}
+
+/// An entry along with the resolved command that opens it, as exposed in the JSON plan
+#[derive(Serialize)]
+struct Action<'a> {
+    entry: &'a Entry,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    program: String,
+    args: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retries: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<&'a str>,
+}
+
+fn serialize_actions<S>(steps: &[&Step], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_seq(steps.iter().map(|step| {
+        let entry = &step.entry;
+        let command = entry.command();
+        Action {
+            entry,
+            kind: entry.kind(),
+            program: command.get_program().to_string_lossy().into_owned(),
+            args: command
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+            retries: step.options.retries,
+            group: step.options.group.as_deref(),
+        }
+    }))
+}
+
+impl<'a> Plan<'a> {
+    pub fn new(name: &'a str, parcel: &'a Parcel, settings: &Settings) -> Self {
+        let mut stages = Vec::new();
+        if !parcel.entries.is_empty() {
+            stages.push(Stage {
+                name: None,
+                limit: settings.concurrency.unwrap_or(1),
+                depends_on: None,
+                on_success: false,
+                entries: parcel.entries.iter().collect(),
+            });
+        }
+
+        for phase in &parcel.phases {
+            let limit = if phase.parallel {
+                settings
+                    .concurrency
+                    .unwrap_or_else(|| thread::available_parallelism().map_or(1, NonZeroUsize::get))
+            } else {
+                1
+            };
+            stages.push(Stage {
+                name: Some(phase.name.as_str()),
+                limit,
+                depends_on: stages.len().checked_sub(1),
+                on_success: false,
+                entries: phase.entries.iter().collect(),
+            });
+        }
+
+        if !parcel.then.is_empty() {
+            stages.push(Stage {
+                name: Some("then"),
+                limit: 1,
+                depends_on: stages.len().checked_sub(1),
+                on_success: true,
+                entries: parcel.then.iter().collect(),
+            });
+        }
+
+        Self {
+            parcel: name,
+            stages,
+            timeout: parcel.timeout,
+            retries: parcel.retries.unwrap_or(0),
+            idempotent: parcel.idempotent,
+            resume: false,
+            missing_path: settings.missing_path,
+        }
+    }
+}
+
+impl Display for Stage<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(name) = self.name {
+            write!(f, "[{}] ", name)?;
+        }
+        match self.limit {
+            0 | 1 => write!(f, "{}", t!("plan-one-at-a-time")),
+            n => write!(f, "{}", t!("plan-at-once", limit = n)),
+        }
+    }
+}
+
+impl Display for Plan<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.timeout {
+            Some(timeout) => writeln!(
+                f,
+                "{} {}",
+                self.parcel,
+                t!("plan-timeout", timeout = duration::format(timeout))
+            )?,
+            None => writeln!(f, "{}", self.parcel)?,
+        }
+        for (i, stage) in self.stages.iter().enumerate() {
+            let last_stage = i + 1 == self.stages.len();
+            let (branch, indent) = if last_stage {
+                ("└──", "    ")
+            } else {
+                ("├──", "│   ")
+            };
+            writeln!(f, "{} {}", branch, stage)?;
+
+            for (j, step) in stage.entries.iter().enumerate() {
+                let branch = if j + 1 == stage.entries.len() {
+                    "└──"
+                } else {
+                    "├──"
+                };
+                writeln!(
+                    f,
+                    "{}{} {}",
+                    indent,
+                    branch,
+                    command_line(&step.entry.command())
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The command line, quoted for display
+pub fn command_line(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| {
+            let arg = arg.to_string_lossy();
+            if arg.contains(char::is_whitespace) {
+                format!("{:?}", arg)
+            } else {
+                arg.into_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,172 @@
+//! Entry types added by WASM modules in `~/.config/kozutsumi/plugins/`.
+//!
+//! A plugin claims the entries starting with a prefix and turns each of them into the command opening it.
+//! It exports:
+//! - `memory`
+//! - `alloc(len: i32) -> i32`, reserving room for an entry passed to `open`
+//! - `prefix() -> i64`, the prefix of the entries it claims
+//! - `open(ptr: i32, len: i32) -> i64`, the command opening the entry, one argument per line
+//!
+//! Strings returned to the host are packed as `(ptr << 32) | len`.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Mutex,
+    time::Instant,
+};
+
+use anyhow::Context as _;
+use wasmtime::{Engine, Instance, Module, Store};
+
+use crate::{
+    config::Entry,
+    i18n::t,
+    opener::{self, Opener, Outcome},
+    plan,
+};
+
+fn plugins_dir() -> PathBuf {
+    PathBuf::from(shellexpand::tilde("~/.config/kozutsumi/plugins").into_owned())
+}
+
+struct Plugin {
+    prefix: String,
+    store: Mutex<Store<()>>,
+    instance: Instance,
+}
+
+impl Plugin {
+    fn load(engine: &Engine, path: &Path) -> anyhow::Result<Self> {
+        let module = Module::from_file(engine, path)?;
+        let mut store = Store::new(engine, ());
+        let instance = Instance::new(&mut store, &module, &[])?;
+        let packed = instance
+            .get_typed_func::<(), i64>(&mut store, "prefix")?
+            .call(&mut store, ())?;
+        let prefix = read_string(&instance, &mut store, packed)?;
+        Ok(Self {
+            prefix,
+            store: Mutex::new(store),
+            instance,
+        })
+    }
+
+    /// Ask the plugin for the command opening the entry
+    fn command(&self, uri: &str) -> anyhow::Result<Command> {
+        let mut store = self.store.lock().unwrap();
+        let store = &mut *store;
+
+        let len = i32::try_from(uri.len())?;
+        let ptr = self
+            .instance
+            .get_typed_func::<i32, i32>(&mut *store, "alloc")?
+            .call(&mut *store, len)?;
+        memory(&self.instance, store)?.write(&mut *store, ptr as usize, uri.as_bytes())?;
+        let packed = self
+            .instance
+            .get_typed_func::<(i32, i32), i64>(&mut *store, "open")?
+            .call(&mut *store, (ptr, len))?;
+
+        let line = read_string(&self.instance, store, packed)?;
+        let mut args = line.lines();
+        let program = args
+            .next()
+            .with_context(|| t!("plugin-no-command", entry = uri))?;
+        let mut command = Command::new(program);
+        command.args(args);
+        Ok(command)
+    }
+}
+
+fn memory(instance: &Instance, store: &mut Store<()>) -> anyhow::Result<wasmtime::Memory> {
+    instance
+        .get_memory(&mut *store, "memory")
+        .context("the plugin doesn't export its memory")
+}
+
+/// Read a string the plugin returned as `(ptr << 32) | len`
+fn read_string(instance: &Instance, store: &mut Store<()>, packed: i64) -> anyhow::Result<String> {
+    let packed = packed as u64;
+    let (ptr, len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+    let mut bytes = vec![0; len];
+    memory(instance, store)?.read(&*store, ptr, &mut bytes)?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// Opens the entries claimed by a plugin through the command it returns,
+/// and every other entry through `fallback`
+pub struct Plugins<'a> {
+    plugins: Vec<Plugin>,
+    fallback: &'a dyn Opener,
+}
+
+impl<'a> Plugins<'a> {
+    /// Load every `.wasm` module of the plugins directory, which doesn't have to exist
+    pub fn load(fallback: &'a dyn Opener) -> anyhow::Result<Self> {
+        let files = match fs::read_dir(plugins_dir()) {
+            Ok(files) => files,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self {
+                    plugins: Vec::new(),
+                    fallback,
+                });
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let engine = Engine::default();
+        let mut paths = files
+            .filter_map(Result::ok)
+            .map(|file| file.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "wasm"))
+            .collect::<Vec<_>>();
+        paths.sort();
+        let plugins = paths
+            .iter()
+            .map(|path| {
+                Plugin::load(&engine, path)
+                    .with_context(|| t!("plugin-invalid", path = path.display()))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { plugins, fallback })
+    }
+
+    /// The plugin with the longest prefix matching the entry, if any
+    fn plugin(&self, uri: &str) -> Option<&Plugin> {
+        self.plugins
+            .iter()
+            .filter(|plugin| uri.starts_with(plugin.prefix.as_str()))
+            .max_by_key(|plugin| plugin.prefix.len())
+    }
+}
+
+impl Opener for Plugins<'_> {
+    fn open(&self, entry: &Entry, deadline: Option<Instant>) -> io::Result<Outcome> {
+        let uri = entry.to_string();
+        match self.plugin(&uri) {
+            Some(plugin) => opener::wait(plugin.command(&uri).map_err(io::Error::other)?, deadline),
+            None => self.fallback.open(entry, deadline),
+        }
+    }
+
+    fn close(&self, entry: &Entry) -> io::Result<()> {
+        self.fallback.close(entry)
+    }
+
+    fn describe(&self, entry: &Entry) -> String {
+        let uri = entry.to_string();
+        match self.plugin(&uri) {
+            Some(plugin) => match plugin.command(&uri) {
+                Ok(command) => plan::command_line(&command),
+                Err(e) => format!("{:#}", e),
+            },
+            None => self.fallback.describe(entry),
+        }
+    }
+
+    fn is_open(&self, entry: &Entry) -> bool {
+        self.fallback.is_open(entry)
+    }
+}
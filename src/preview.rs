@@ -0,0 +1,100 @@
+//! Previews for the dialoguer chooser, which has no preview pane like fzf's.
+//!
+//! The theme wrapping the chooser's writes the preview of the active item under it,
+//! and dialoguer clears the extra lines along with the item when the cursor moves.
+
+use std::{collections::HashMap, fmt};
+
+use dialoguer::theme::Theme;
+use fuzzy_matcher::skim::SkimMatcherV2;
+
+/// A theme rendering items like `theme`, with the preview of the active one below it
+pub struct Previewed<'a> {
+    pub theme: &'a dyn Theme,
+    /// Previews by the text of their item
+    pub previews: HashMap<String, String>,
+}
+
+impl Previewed<'_> {
+    fn preview(&self, f: &mut dyn fmt::Write, text: &str, active: bool) -> fmt::Result {
+        let Some(preview) = self.previews.get(text).filter(|_| active) else {
+            return Ok(());
+        };
+        for line in preview.trim_end().lines() {
+            write!(f, "\n      {}", line)?;
+        }
+        Ok(())
+    }
+}
+
+impl Theme for Previewed<'_> {
+    fn format_error(&self, f: &mut dyn fmt::Write, err: &str) -> fmt::Result {
+        self.theme.format_error(f, err)
+    }
+
+    fn format_select_prompt_selection(
+        &self,
+        f: &mut dyn fmt::Write,
+        prompt: &str,
+        sel: &str,
+    ) -> fmt::Result {
+        self.theme.format_select_prompt_selection(f, prompt, sel)
+    }
+
+    fn format_multi_select_prompt(&self, f: &mut dyn fmt::Write, prompt: &str) -> fmt::Result {
+        self.theme.format_multi_select_prompt(f, prompt)
+    }
+
+    fn format_multi_select_prompt_selection(
+        &self,
+        f: &mut dyn fmt::Write,
+        prompt: &str,
+        selections: &[&str],
+    ) -> fmt::Result {
+        self.theme
+            .format_multi_select_prompt_selection(f, prompt, selections)
+    }
+
+    fn format_multi_select_prompt_item(
+        &self,
+        f: &mut dyn fmt::Write,
+        text: &str,
+        checked: bool,
+        active: bool,
+    ) -> fmt::Result {
+        self.theme
+            .format_multi_select_prompt_item(f, text, checked, active)?;
+        self.preview(f, text, active)
+    }
+
+    fn format_fuzzy_select_prompt(
+        &self,
+        f: &mut dyn fmt::Write,
+        prompt: &str,
+        search_term: &str,
+        bytes_pos: usize,
+    ) -> fmt::Result {
+        self.theme
+            .format_fuzzy_select_prompt(f, prompt, search_term, bytes_pos)
+    }
+
+    fn format_fuzzy_select_prompt_item(
+        &self,
+        f: &mut dyn fmt::Write,
+        text: &str,
+        active: bool,
+        highlight_matches: bool,
+        matcher: &SkimMatcherV2,
+        search_term: &str,
+    ) -> fmt::Result {
+        self.theme.format_fuzzy_select_prompt_item(
+            f,
+            text,
+            active,
+            highlight_matches,
+            matcher,
+            search_term,
+        )?;
+        self.preview(f, text, active)
+    }
+}
@@ -0,0 +1,123 @@
+//! Processes of detached entries, tracked per parcel in the state directory
+//! so that `stop` can terminate them.
+
+use std::{
+    io,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::state::Store;
+
+/// How long processes get to exit after SIGTERM, before being sent SIGKILL
+const GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// A process started by a detached entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Process {
+    pub pid: u32,
+    /// The entry that started it, written like in the config
+    pub entry: String,
+}
+
+/// The processes started by the parcel
+fn store(parcel: &str) -> Store<Vec<Process>> {
+    Store::state(Path::new("pids").join(parcel.replace('/', "%2F")))
+}
+
+/// Record the processes started by opening a parcel, along with the ones of previous opens still running
+pub fn record(parcel: &str, processes: &[Process]) -> io::Result<()> {
+    if processes.is_empty() {
+        return Ok(());
+    }
+
+    let store = store(parcel);
+    let mut tracked = store.load()?.unwrap_or_default();
+    tracked.retain(|process| is_running(process.pid));
+    tracked.extend_from_slice(processes);
+    store.save(&tracked)
+}
+
+/// The processes started by the parcel that are still running
+pub fn running(parcel: &str) -> io::Result<Vec<Process>> {
+    let mut tracked = store(parcel).load()?.unwrap_or_default();
+    tracked.retain(|process| is_running(process.pid));
+    Ok(tracked)
+}
+
+/// Terminate the processes started by the parcel, returning how many were still running.
+///
+/// Each one is sent SIGTERM, then SIGKILL if it hasn't exited after [`GRACE_PERIOD`].
+pub fn stop(parcel: &str) -> io::Result<usize> {
+    let store = store(parcel);
+    let running = store
+        .load()?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|process| process.pid)
+        .filter(|&pid| is_running(pid))
+        .collect::<Vec<_>>();
+    terminate(&running);
+
+    store.remove()?;
+    Ok(running.len())
+}
+
+/// Send SIGTERM to the processes, then SIGKILL to the ones still running after [`GRACE_PERIOD`]
+pub fn terminate(running: &[u32]) {
+    for &pid in running {
+        signal(pid, "TERM");
+    }
+    let deadline = Instant::now() + GRACE_PERIOD;
+    while running.iter().any(|&pid| is_running(pid)) && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(100));
+    }
+    for &pid in running {
+        if is_running(pid) {
+            signal(pid, "KILL");
+        }
+    }
+}
+
+/// Send a signal to the process group of a detached entry, which includes the processes it started.
+///
+/// Returns whether the group exists.
+fn signal(pid: u32, signal: &str) -> bool {
+    Command::new("kill")
+        .arg(format!("-{}", signal))
+        .arg("--")
+        .arg(format!("-{}", pid))
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Whether a process with exactly this name is running
+pub fn is_running_named(name: &str) -> bool {
+    Command::new("pgrep")
+        .arg("-x")
+        .arg(name)
+        .stdout(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Whether a process has the file open
+pub fn is_file_open(path: &Path) -> bool {
+    Command::new("lsof")
+        .arg("-t")
+        .arg("--")
+        .arg(path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+fn is_running(pid: u32) -> bool {
+    signal(pid, "0")
+}
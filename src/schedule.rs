@@ -0,0 +1,162 @@
+//! Parcels opened on a cron-like `schedule`, through one LaunchAgent per parcel in `~/Library/LaunchAgents`.
+//!
+//! `schedule apply` turns each schedule into the calendar intervals of its agent.
+
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::Context as _;
+
+use crate::{autostart, config::ParcelConfig, i18n::t};
+
+/// Prefix of the labels of the LaunchAgents, followed by the name of the parcel.
+/// It doesn't start like the prefix of the agents of `autostart`, so that they aren't listed as such
+const LABEL_PREFIX: &str = "com.github.fruit-bird.kozutsumi-schedule.";
+
+/// The fields of a cron expression in order, as the launchd key each one maps to and the range of its values
+const FIELDS: [(&str, u32, u32); 5] = [
+    ("Minute", 0, 59),
+    ("Hour", 0, 23),
+    ("Day", 1, 31),
+    ("Month", 1, 12),
+    ("Weekday", 0, 7),
+];
+
+/// Values of a calendar interval, by launchd key. Keys left out match every value
+type Interval = Vec<(&'static str, u32)>;
+
+/// The calendar intervals of a cron expression like `0 9 * * 1-5`, one per combination of the values of its fields.
+///
+/// Fields can be `*`, numbers, ranges like `1-5`, lists like `1,3`, and steps like `*/15`.
+/// As with launchd, a day of the month and a weekday both have to match, unlike with cron.
+pub fn intervals(cron: &str) -> anyhow::Result<Vec<Interval>> {
+    let fields = cron.split_whitespace().collect::<Vec<_>>();
+    if fields.len() != FIELDS.len() {
+        anyhow::bail!(t!("schedule-invalid", schedule = cron));
+    }
+
+    let mut intervals = vec![Interval::new()];
+    for (field, (key, min, max)) in fields.into_iter().zip(FIELDS) {
+        if field == "*" {
+            continue;
+        }
+        let mut values = parse_field(field, min, max)
+            .with_context(|| t!("schedule-invalid", schedule = cron))?;
+        if key == "Weekday" {
+            // Both 0 and 7 are Sunday
+            values = values.into_iter().map(|value| value % 7).collect();
+            values.sort();
+            values.dedup();
+        }
+        intervals = intervals
+            .into_iter()
+            .flat_map(|interval| {
+                values.iter().map(move |&value| {
+                    let mut interval = interval.clone();
+                    interval.push((key, value));
+                    interval
+                })
+            })
+            .collect();
+    }
+    Ok(intervals)
+}
+
+/// Values of a field of a cron expression, or `None` if it isn't valid
+fn parse_field(field: &str, min: u32, max: u32) -> Option<Vec<u32>> {
+    let mut values = Vec::new();
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<usize>().ok().filter(|&step| step > 0)?),
+            None => (part, 1),
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+            // `5/15` starts at 5 and goes on until the end of the range
+            None if step > 1 => (range.parse().ok()?, max),
+            None => {
+                let value = range.parse().ok()?;
+                (value, value)
+            }
+        };
+        if start < min || end > max || start > end {
+            return None;
+        }
+        values.extend((start..=end).step_by(step));
+    }
+    values.sort();
+    values.dedup();
+    Some(values)
+}
+
+/// The launchd keys running the agent at the intervals
+fn trigger(intervals: &[Interval]) -> String {
+    let dicts = intervals
+        .iter()
+        .map(|interval| {
+            let keys = interval
+                .iter()
+                .map(|(key, value)| {
+                    format!(
+                        "            <key>{}</key>\n            <integer>{}</integer>\n",
+                        key, value
+                    )
+                })
+                .collect::<String>();
+            format!("        <dict>\n{}        </dict>\n", keys)
+        })
+        .collect::<String>();
+    format!(
+        "    <key>StartCalendarInterval</key>\n    <array>\n{}    </array>",
+        dicts
+    )
+}
+
+/// Install and load a LaunchAgent for every parcel with a schedule, and remove the ones of parcels without one.
+///
+/// Every schedule is checked before any agent is written.
+pub fn apply(config: &ParcelConfig, config_path: &Path) -> anyhow::Result<()> {
+    let scheduled = config
+        .parcels
+        .iter()
+        .filter_map(|(name, parcel)| Some((name.as_str(), parcel.schedule.as_deref()?)))
+        .map(|(name, cron)| {
+            let intervals =
+                intervals(cron).with_context(|| t!("schedule-invalid-parcel", name = name))?;
+            Ok((name, (cron, intervals)))
+        })
+        .collect::<anyhow::Result<BTreeMap<_, _>>>()?;
+
+    for (name, (cron, intervals)) in &scheduled {
+        let label = autostart::label(LABEL_PREFIX, name);
+        let path = autostart::plist_path(&label);
+        autostart::write_agent(&path, &label, name, config_path, &trigger(intervals))?;
+        // The agent only opens the parcel at its intervals, so it can be loaded right away
+        autostart::unload(&label);
+        Command::new("launchctl")
+            .arg("bootstrap")
+            .arg(autostart::gui_domain())
+            .arg(&path)
+            .output()?;
+        println!("{}", t!("schedule-applied", name = name, schedule = cron));
+    }
+
+    for (name, path) in installed()? {
+        if !scheduled.contains_key(name.as_str()) {
+            autostart::unload(&autostart::label(LABEL_PREFIX, &name));
+            std::fs::remove_file(&path)?;
+            println!("{}", t!("schedule-removed", name = name));
+        }
+    }
+    Ok(())
+}
+
+/// Names of the parcels that have a schedule agent, along with the path of the agent
+pub fn installed() -> io::Result<Vec<(String, PathBuf)>> {
+    autostart::installed_with_prefix(LABEL_PREFIX)
+}
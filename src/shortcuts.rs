@@ -0,0 +1,96 @@
+//! "Open Parcel" shortcuts for the Shortcuts app, so parcels can be opened from automations, HomeKit scenes,
+//! and the other places shortcuts run from.
+//!
+//! Each shortcut runs `kozutsumi open <name>` as a shell script. It is signed with `shortcuts sign`,
+//! then opened so that Shortcuts offers to add it.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
+
+use crate::{autostart, i18n::t};
+
+/// Quote text for a shell script
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// The unsigned shortcut running the script, as a plist
+fn workflow(script: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>WFWorkflowActions</key>
+    <array>
+        <dict>
+            <key>WFWorkflowActionIdentifier</key>
+            <string>is.workflow.actions.runshellscript</string>
+            <key>WFWorkflowActionParameters</key>
+            <dict>
+                <key>Script</key>
+                <string>{}</string>
+                <key>Shell</key>
+                <string>/bin/sh</string>
+            </dict>
+        </dict>
+    </array>
+    <key>WFWorkflowClientVersion</key>
+    <string>2302.0.4</string>
+    <key>WFWorkflowMinimumClientVersion</key>
+    <integer>900</integer>
+    <key>WFWorkflowImportQuestions</key>
+    <array/>
+    <key>WFWorkflowInputContentItemClasses</key>
+    <array/>
+    <key>WFWorkflowTypes</key>
+    <array/>
+</dict>
+</plist>
+"#,
+        autostart::escape(script)
+    )
+}
+
+/// Sign a shortcut opening each parcel, and open them so that Shortcuts offers to add them.
+/// Returns the signed shortcuts
+pub fn install(names: &[&str], config_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let exe = env::current_exe()?;
+    let config_path = config_path.canonicalize()?;
+    let dir = env::temp_dir().join(format!("kozutsumi-shortcuts-{}", process::id()));
+    fs::create_dir_all(&dir)?;
+
+    let mut signed = Vec::new();
+    for name in names {
+        let script = format!(
+            "{} --config {} open {}",
+            quote(&exe.to_string_lossy()),
+            quote(&config_path.to_string_lossy()),
+            quote(name)
+        );
+        // Shortcuts names the shortcut after its file
+        let file_name = format!(
+            "{}.shortcut",
+            t!("shortcut-name", name = name.replace('/', "-"))
+        );
+        let unsigned = dir.join(format!("unsigned-{}", file_name));
+        let path = dir.join(file_name);
+        fs::write(&unsigned, workflow(&script))?;
+
+        let status = Command::new("shortcuts")
+            .args(["sign", "--mode", "anyone", "--input"])
+            .arg(&unsigned)
+            .arg("--output")
+            .arg(&path)
+            .status()?;
+        if !status.success() {
+            anyhow::bail!(t!("shortcut-sign-failed", name = name, status = status));
+        }
+        Command::new("open").arg(&path).status()?;
+        signed.push(path);
+    }
+    Ok(signed)
+}
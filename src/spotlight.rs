@@ -0,0 +1,83 @@
+//! `mdfind:` entries, opening the documents found by a Spotlight query.
+
+use std::{io, path::PathBuf, process::Command};
+
+use crate::{
+    config::{Entry, Parcel, ParcelConfig},
+    i18n::t,
+};
+
+/// Most results offered to choose from, the others are left out
+const MAX_RESULTS: usize = 20;
+
+/// Paths found by the query, at most [`MAX_RESULTS`] of them
+fn search(query: &str) -> io::Result<Vec<PathBuf>> {
+    let output = Command::new("mdfind").arg(query).output()?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.is_empty())
+        .take(MAX_RESULTS)
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// Replace the Spotlight queries of the parcel by the file they find,
+/// asking which one to open when several match.
+///
+/// Queries that find nothing are left as they are, and open nothing
+pub fn resolve(config: &ParcelConfig, parcel: &mut Parcel) -> anyhow::Result<()> {
+    for entry in parcel.entries_mut() {
+        let Entry::Spotlight(query) = entry else {
+            continue;
+        };
+        let mut results = search(query)?;
+        let path = match results.len() {
+            0 => {
+                eprintln!("{}", t!("spotlight-no-results", query = query));
+                continue;
+            }
+            1 => results.remove(0),
+            _ => {
+                let i = choose(config, query, &results)?;
+                results.swap_remove(i)
+            }
+        };
+        *entry = Entry::File(path);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "dialog")]
+fn choose(config: &ParcelConfig, query: &str, results: &[PathBuf]) -> io::Result<usize> {
+    let items = results
+        .iter()
+        .map(|path| path.display())
+        .collect::<Vec<_>>();
+    dialoguer::Select::with_theme(&*crate::utils::dialog_theme(&config.settings))
+        .with_prompt(query)
+        .items(&items)
+        .default(0)
+        .interact()
+        .map_err(io::Error::other)
+}
+
+/// Ask for the number of the result to open, the first one if nothing valid is typed
+#[cfg(not(feature = "dialog"))]
+fn choose(_config: &ParcelConfig, query: &str, results: &[PathBuf]) -> io::Result<usize> {
+    use std::io::{BufRead, Write};
+
+    eprintln!("{}", query);
+    for (i, path) in results.iter().enumerate() {
+        eprintln!("  {}) {}", i + 1, path.display());
+    }
+    eprint!("{} ", t!("spotlight-choose"));
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(answer
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .filter(|n| (1..=results.len()).contains(n))
+        .map_or(0, |n| n - 1))
+}
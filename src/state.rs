@@ -0,0 +1,127 @@
+//! Data kept between runs: state in `~/.local/state/kozutsumi`, and caches in `~/.cache/kozutsumi`.
+//!
+//! Each piece of data lives in a [`Store`], a file holding a single typed value written as YAML.
+//! A [`Lock`] keeps two runs from doing the same thing at once.
+
+use std::{
+    env,
+    fs::{self, File, TryLockError},
+    io,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, SystemTime},
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::utils;
+
+/// Directory of data that should survive between runs, such as tracked processes and backups
+pub fn state_dir() -> PathBuf {
+    if cfg!(test) {
+        return test_dir().join("state");
+    }
+    PathBuf::from(shellexpand::tilde("~/.local/state/kozutsumi").into_owned())
+}
+
+/// Directory of data that can be deleted at any time, and recomputed when needed
+pub fn cache_dir() -> PathBuf {
+    if cfg!(test) {
+        return test_dir().join("cache");
+    }
+    PathBuf::from(shellexpand::tilde("~/.cache/kozutsumi").into_owned())
+}
+
+/// A temporary directory of the test run, so that tests never read or write the user's state
+fn test_dir() -> PathBuf {
+    env::temp_dir().join(format!("kozutsumi-test-{}", process::id()))
+}
+
+/// A value of type `T` persisted in a file of its own
+pub struct Store<T> {
+    path: PathBuf,
+    value: PhantomData<fn() -> T>,
+}
+
+impl<T> Store<T> {
+    /// A store in the state directory, at a path like `pids/dev`
+    pub fn state(name: impl AsRef<Path>) -> Self {
+        Self::at(state_dir().join(name))
+    }
+
+    /// A store in the cache directory, at a path like `latest-version`
+    pub fn cache(name: impl AsRef<Path>) -> Self {
+        Self::at(cache_dir().join(name))
+    }
+
+    fn at(path: PathBuf) -> Self {
+        Self {
+            path,
+            value: PhantomData,
+        }
+    }
+
+    /// Time since the value was last saved, if it ever was
+    pub fn age(&self) -> Option<Duration> {
+        let modified = fs::metadata(&self.path)
+            .and_then(|meta| meta.modified())
+            .ok()?;
+        SystemTime::now().duration_since(modified).ok()
+    }
+
+    /// Delete the value, if it exists
+    pub fn remove(&self) -> io::Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Store<T> {
+    /// The saved value, or `None` if nothing was saved yet
+    pub fn load(&self) -> io::Result<Option<T>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        serde_yaml::from_str(&contents)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl<T: Serialize> Store<T> {
+    /// Replace the saved value, creating the directories it lives in if needed
+    pub fn save(&self, value: &T) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let contents = serde_yaml::to_string(value)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        utils::write_atomic(&self.path, contents)
+    }
+}
+
+/// An advisory lock on a file of the state directory, released when dropped or when the process exits
+pub struct Lock {
+    _file: File,
+}
+
+impl Lock {
+    /// Take the lock at a path like `locks/dev`, or return `None` if another process holds it
+    pub fn try_acquire(name: impl AsRef<Path>) -> io::Result<Option<Self>> {
+        let path = state_dir().join(name);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = File::create(path)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Some(Self { _file: file })),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(e)) => Err(e),
+        }
+    }
+}
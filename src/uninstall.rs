@@ -0,0 +1,77 @@
+//! Removal of everything the tool created outside of the config.
+
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    path::PathBuf,
+};
+
+#[cfg(feature = "completions")]
+use crate::completions;
+use crate::{autostart, i18n::t, schedule, state};
+
+/// Directories the tool keeps its state and caches in, that currently exist
+fn data_dirs() -> Vec<PathBuf> {
+    [state::state_dir(), state::cache_dir()]
+        .into_iter()
+        .filter(|dir| dir.exists())
+        .collect()
+}
+
+/// List everything the tool created, and delete it once confirmed (or right away with `yes`).
+///
+/// The config itself is left alone, since it is written by the user.
+pub fn run(yes: bool) -> anyhow::Result<()> {
+    let mut agents = autostart::installed()?;
+    agents.extend(schedule::installed()?);
+    // Read before the state directory, where they are tracked, is removed
+    #[cfg(feature = "completions")]
+    let scripts = completions::installed()?;
+    #[cfg(not(feature = "completions"))]
+    let scripts = Vec::<PathBuf>::new();
+    let dirs = data_dirs();
+    if agents.is_empty() && scripts.is_empty() && dirs.is_empty() {
+        println!("{}", t!("uninstall-nothing"));
+        return Ok(());
+    }
+
+    println!("{}", t!("uninstall-listing"));
+    for path in agents
+        .iter()
+        .map(|(_, path)| path)
+        .chain(&scripts)
+        .chain(&dirs)
+    {
+        println!("  {}", path.display());
+    }
+
+    if !yes {
+        print!("{} ", t!("uninstall-confirm"));
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        io::stdin().lock().read_line(&mut answer)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            println!("{}", t!("uninstall-aborted"));
+            return Ok(());
+        }
+    }
+
+    // Agents are named after their label
+    for (_, path) in &agents {
+        if let Some(label) = path.file_stem() {
+            autostart::unload(&label.to_string_lossy());
+        }
+        fs::remove_file(path)?;
+        println!("{}", t!("uninstall-removed", path = path.display()));
+    }
+    for path in &scripts {
+        fs::remove_file(path)?;
+        println!("{}", t!("uninstall-removed", path = path.display()));
+    }
+    for dir in &dirs {
+        fs::remove_dir_all(dir)?;
+        println!("{}", t!("uninstall-removed", path = dir.display()));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+//! Opt-in check for newer releases, done at most once a day.
+
+use std::{
+    io::{self, IsTerminal},
+    process::Command,
+    time::Duration,
+};
+
+use crate::{i18n::t, state::Store};
+
+/// How long the latest known version is trusted before asking GitHub again
+const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+fn cache() -> Store<String> {
+    Store::cache("latest-version")
+}
+
+/// Print a one-line upgrade hint to stderr if a newer release exists.
+///
+/// Failures (no network, no curl...) are silently ignored, this must never get in the way.
+pub fn notice() {
+    if !io::stderr().is_terminal() {
+        return;
+    }
+
+    let current = env!("CARGO_PKG_VERSION");
+    if let Some(latest) = latest_version()
+        && parse(&latest) > parse(current)
+    {
+        eprintln!(
+            "{}",
+            t!("update-available", current = current, latest = latest)
+        );
+    }
+}
+
+/// The latest released version, from the cache if it is fresh enough
+fn latest_version() -> Option<String> {
+    let cache = cache();
+    if cache.age().is_some_and(|age| age < CACHE_TTL)
+        && let Ok(Some(latest)) = cache.load()
+    {
+        return Some(latest);
+    }
+
+    let latest = fetch_latest_version()?;
+    let _ = cache.save(&latest);
+    Some(latest)
+}
+
+/// Ask GitHub for the latest release, which redirects to `.../releases/tag/v<version>`
+fn fetch_latest_version() -> Option<String> {
+    let output = Command::new("curl")
+        .args(["--silent", "--max-time", "2", "--output", "/dev/null"])
+        .args(["--write-out", "%{redirect_url}"])
+        .arg(format!("{}/releases/latest", env!("CARGO_PKG_REPOSITORY")))
+        .output()
+        .ok()?;
+
+    let url = String::from_utf8(output.stdout).ok()?;
+    let tag = url.trim().rsplit_once("/tag/")?.1;
+    Some(tag.trim_start_matches('v').to_string())
+}
+
+/// Numeric components of a version, so that `0.10.0` > `0.9.1`
+fn parse(version: &str) -> Vec<u64> {
+    version
+        .trim()
+        .split('.')
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
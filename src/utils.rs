@@ -1,12 +1,23 @@
 use std::{
     env,
-    io::Write as _,
-    path::Path,
-    process::{Command, Stdio},
-    vec,
+    fs::{self, File},
+    io::{self, BufRead, IsTerminal, Write as _},
+    path::{Path, PathBuf},
+    process::{self, Command, Stdio},
+    thread, vec,
 };
 
-use crate::{cli::ParcelCommands, config::ParcelConfig};
+use crate::{
+    cli::{Chooser, OpenArgs, ParcelCommands},
+    config::{Entry, Matching, Parcel, ParcelConfig, Settings, Step, Style},
+    engine,
+    error::ParcelError,
+    i18n::t,
+    opener::Opener,
+    output::{self, Printer},
+    placeholders,
+    plan::Plan,
+};
 
 pub fn default_config() -> String {
     let base = shellexpand::tilde("~/.config/kozutsumi/parcel");
@@ -16,63 +27,430 @@ pub fn default_config() -> String {
     if Path::new(&yml).exists() { yml } else { yaml }
 }
 
-pub fn available_parcels(config: &ParcelConfig) -> String {
-    config
+/// Replace the contents of a file without ever leaving it half-written.
+///
+/// The contents go to a temporary file in the same directory, which is then renamed over the original.
+/// Symlinks are followed, so a config symlinked from a dotfiles repo stays a symlink.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let tmp = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        process::id()
+    ));
+
+    let write = |tmp: &PathBuf| {
+        let mut file = File::create(tmp)?;
+        if let Ok(meta) = fs::metadata(&path) {
+            file.set_permissions(meta.permissions())?;
+        }
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        fs::rename(tmp, &path)
+    };
+
+    write(&tmp).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+/// Look up a parcel by name, or by a shorter form of it allowed by `settings.matching`.
+/// Names are compared regardless of case and accents.
+///
+/// Returns the full name of the parcel along with it, and suggests the closest ones if none matches.
+pub fn find_parcel<'a>(
+    config: &'a ParcelConfig,
+    name: &str,
+) -> Result<(&'a str, &'a Parcel), ParcelError> {
+    if let Some((name, parcel)) = config.parcels.get_key_value(name) {
+        return Ok((name, parcel));
+    }
+
+    let query = normalize(name);
+    for (_, rule) in MATCHING_RULES
+        .iter()
+        .filter(|(level, _)| *level <= config.settings.matching)
+    {
+        let mut matches = config
+            .parcels
+            .iter()
+            .filter(|(candidate, _)| rule(&normalize(candidate), &query))
+            .collect::<Vec<_>>();
+        match matches.as_slice() {
+            [] => continue,
+            [(candidate, parcel)] => return Ok((candidate.as_str(), parcel)),
+            _ => {
+                matches.sort_by_key(|(candidate, _)| candidate.as_str());
+                return Err(ParcelError::ParcelAmbiguous {
+                    name: name.to_string(),
+                    matches: matches
+                        .iter()
+                        .map(|(candidate, _)| candidate.to_string())
+                        .collect(),
+                });
+            }
+        }
+    }
+
+    // The parcels a looser matching would have found, or all of them if none would
+    let mut suggestions = config
         .parcels
         .keys()
+        .filter(|candidate| {
+            let candidate = normalize(candidate);
+            MATCHING_RULES
+                .iter()
+                .any(|(_, rule)| rule(&candidate, &query))
+        })
         .cloned()
-        .collect::<Vec<_>>()
-        .join(", ")
+        .collect::<Vec<_>>();
+    if suggestions.is_empty() {
+        suggestions = config.parcels.keys().cloned().collect();
+    }
+    suggestions.sort();
+    Err(ParcelError::ParcelNotFound {
+        name: name.to_string(),
+        suggestions,
+    })
+}
+
+/// Whether a normalized parcel name matches a normalized name
+type Rule = fn(&str, &str) -> bool;
+
+/// The rules a name matches a parcel by, from the strictest, along with the `settings.matching` they need
+const MATCHING_RULES: [(Matching, Rule); 5] = [
+    (Matching::Exact, |candidate, name| candidate == name),
+    (Matching::Prefix, |candidate, name| {
+        candidate.starts_with(name)
+    }),
+    (Matching::Substring, |candidate, name| {
+        candidate.contains(name)
+    }),
+    (Matching::Abbreviation, |candidate, name| {
+        candidate
+            .split(['-', '_', ' ', '.'])
+            .filter_map(|word| word.chars().next())
+            .eq(name.chars())
+    }),
+    (Matching::Abbreviation, |candidate, name| {
+        let mut letters = candidate.chars();
+        name.chars().all(|c| letters.any(|letter| letter == c))
+    }),
+];
+
+/// Fold the case and accents of a parcel name, so that `Café` and `cafe` are the same name
+fn normalize(name: &str) -> String {
+    use unicode_normalization::{UnicodeNormalization, char::is_combining_mark};
+
+    name.nfd()
+        .filter(|&c| !is_combining_mark(c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// The parcels with entries matching the query, sorted by name, along with the entries.
+///
+/// Entries match when the query parses to the same entry, or when they contain it regardless of case
+pub fn parcels_containing<'a>(
+    config: &'a ParcelConfig,
+    query: &str,
+) -> Vec<(&'a str, Vec<&'a Entry>)> {
+    let parsed = query.parse::<Entry>().ok().map(|mut entry| {
+        config.resolve_entry(&mut entry);
+        entry.to_string()
+    });
+    let needle = query.to_lowercase();
+    let matches = |entry: &Entry| {
+        let text = entry.to_string();
+        parsed.as_ref() == Some(&text) || text.to_lowercase().contains(&needle)
+    };
+
+    let mut found = config
+        .parcels
+        .iter()
+        .map(|(name, parcel)| {
+            (
+                name.as_str(),
+                parcel.entries().filter(|e| matches(e)).collect::<Vec<_>>(),
+            )
+        })
+        .filter(|(_, entries)| !entries.is_empty())
+        .collect::<Vec<_>>();
+    found.sort_by_key(|(name, _)| *name);
+    found
+}
+
+/// Read an unnamed parcel from entry strings, one per line. Blank lines are ignored
+pub fn read_parcel(config: &ParcelConfig, reader: impl BufRead) -> anyhow::Result<Parcel> {
+    let mut entries = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut entry = line.trim().parse::<Entry>()?;
+        config.resolve_entry(&mut entry);
+        entries.push(entry.into());
+    }
+
+    Ok(Parcel {
+        entries,
+        ..Default::default()
+    })
+}
+
+/// Name of a parcel as shown in choosers, prefixed with its icon if it has one
+pub fn label(name: &str, parcel: &Parcel) -> String {
+    match parcel.text_icon() {
+        Some(icon) => format!("{} {}", icon, name),
+        None => name.to_string(),
+    }
+}
+
+/// Label of a parcel followed by its number of entries, tags and description, dimmed when `color` is set
+pub fn chooser_label(name: &str, parcel: &Parcel, color: bool) -> String {
+    let mut details = vec![format!(
+        "({})",
+        t!("completion-entries", count = parcel.entries().count())
+    )];
+    details.extend(parcel.tags.iter().map(|tag| format!("#{}", tag)));
+    details.extend(parcel.description.clone());
+    let details = output::paint(&Style("dim".to_string()), &details.join("  "), color);
+    format!("{}  {}", label(name, parcel), details)
+}
+
+/// Open only the given entries of a parcel
+fn open_entries(
+    config: &ParcelConfig,
+    name: &str,
+    entries: Vec<Entry>,
+    opener: &dyn Opener,
+) -> anyhow::Result<()> {
+    if entries.is_empty() {
+        eprintln!("{}", t!("no-entries-selected"));
+        return Ok(());
+    }
+
+    let mut parcel = Parcel {
+        entries: entries.into_iter().map(Step::from).collect(),
+        ..Default::default()
+    };
+    placeholders::fill(config, &mut parcel)?;
+    engine::run(&Plan::new(name, &parcel, &config.settings), opener)
+}
+
+/// Open the chosen parcels side by side, and report every one that failed once they are all done
+fn open_parcels(config: &ParcelConfig, names: &[&str], opener: &dyn Opener) -> anyhow::Result<()> {
+    if let [name] = names {
+        return ParcelCommands::open(config, name, &OpenArgs::default(), opener);
+    }
+
+    // Placeholders and Spotlight choices are asked for here, one parcel after the other,
+    // as the parcels can't share stdin once they open side by side
+    let args = OpenArgs::default();
+    let mut failures = Vec::new();
+    let mut openings = Vec::new();
+    for &name in names {
+        match ParcelCommands::begin(config, name, &args) {
+            Ok(Some(opening)) => openings.push((name, opening)),
+            Ok(None) => {}
+            Err(e) if e.is::<engine::Interrupted>() => return Err(e),
+            Err(e) => failures.push((name, e)),
+        }
+    }
+
+    failures.extend(thread::scope(|s| {
+        let handles = openings
+            .into_iter()
+            .map(|(name, opening)| {
+                let args = &args;
+                let handle = s.spawn(move || opening.finish(config, args, opener));
+                (name, handle)
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .filter_map(|(name, handle)| match handle.join() {
+                Ok(opened) => opened.err().map(|e| (name, e)),
+                Err(panic) => std::panic::resume_unwind(panic),
+            })
+            .collect::<Vec<_>>()
+    }));
+    if failures.is_empty() {
+        return Ok(());
+    }
+    if failures.iter().any(|(_, e)| e.is::<engine::Interrupted>()) {
+        return Err(engine::Interrupted.into());
+    }
+
+    for (name, e) in &failures {
+        eprintln!(
+            "{}",
+            t!(
+                "parcel-open-failed",
+                name = name,
+                reason = format!("{:#}", e)
+            )
+        );
+    }
+    anyhow::bail!(t!(
+        "parcels-failed",
+        count = failures.len(),
+        total = names.len()
+    ))
 }
 
+/// Whether the program can be found in `PATH`
+pub fn command_exists(program: &str) -> bool {
+    Command::new("sh")
+        .arg("-c")
+        .arg(r#"command -v "$0""#)
+        .arg(program)
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Show a system notification with the given message
+pub fn notify(message: &str) -> anyhow::Result<()> {
+    Command::new("osascript")
+        .arg("-e")
+        .arg(format!(
+            "display notification {:?} with title {:?}",
+            message,
+            env!("CARGO_PKG_NAME")
+        ))
+        .output()?;
+    Ok(())
+}
+
+/// The dialoguer theme matching the output settings
 #[cfg(feature = "dialog")]
-pub fn choose(config_path: &Path, multi: bool) -> anyhow::Result<()> {
-    use dialoguer::{FuzzySelect, MultiSelect, theme::ColorfulTheme};
+pub fn dialog_theme(settings: &Settings) -> Box<dyn dialoguer::theme::Theme> {
+    use dialoguer::{
+        console::Style,
+        theme::{ColorfulTheme, SimpleTheme},
+    };
+
+    if !output::use_color(settings, &io::stderr()) {
+        return Box::new(SimpleTheme);
+    }
+
+    let theme = &settings.theme;
+    Box::new(ColorfulTheme {
+        active_item_style: Style::from_dotted_str(&theme.parcel.0),
+        values_style: Style::from_dotted_str(&theme.parcel.0),
+        error_style: Style::from_dotted_str(&theme.error.0),
+        ..ColorfulTheme::default()
+    })
+}
+
+/// The selection of a dialoguer prompt, treating Ctrl-C like Esc as fzf does
+#[cfg(feature = "dialog")]
+fn cancellable<T>(selection: dialoguer::Result<Option<T>>) -> anyhow::Result<Option<T>> {
+    match selection {
+        Err(dialoguer::Error::IO(e)) if e.kind() == io::ErrorKind::Interrupted => Ok(None),
+        selection => Ok(selection?),
+    }
+}
+
+#[cfg(feature = "dialog")]
+pub fn choose(config: &ParcelConfig, multi: bool, opener: &dyn Opener) -> anyhow::Result<()> {
+    use dialoguer::{FuzzySelect, MultiSelect};
+
+    use crate::preview::Previewed;
 
-    let config = ParcelConfig::load(config_path)?;
     let parcels = config.parcels.keys().collect::<Vec<_>>();
     if parcels.is_empty() {
-        eprintln!("No parcels available. Please add parcels to the configuration file.");
+        eprintln!("{}", t!("no-parcels"));
         return Ok(());
     }
+    let color = output::use_color(&config.settings, &io::stderr());
+    let labels = parcels
+        .iter()
+        .map(|&name| chooser_label(name, &config.parcels[name], color))
+        .collect::<Vec<_>>();
 
-    if multi {
-        let selection = MultiSelect::with_theme(&ColorfulTheme::default())
-            .with_prompt("Select a parcel to open")
-            .items(&parcels)
-            .interact_opt()?;
-
-        if let Some(indices) = selection {
-            for name in indices.iter().map(|&i| parcels[i].to_string()) {
-                ParcelCommands::Open { name }.run(config_path.as_ref())?;
-            }
-        } else {
-            println!("No parcels selected.");
-        }
-    } else {
-        let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
-            .with_prompt("Select a parcel to open")
-            .items(&parcels)
-            .default(0)
-            .interact_opt()?;
-
-        if let Some(index) = selection {
-            let name = parcels[index].to_string();
-            ParcelCommands::Open { name }.run(config_path.as_ref())?;
-        } else {
-            println!("No parcel selected.");
-        }
+    let theme = dialog_theme(&config.settings);
+    let printer = Printer::with_color(&config.settings, color);
+    let theme = Previewed {
+        theme: theme.as_ref(),
+        previews: labels
+            .iter()
+            .zip(&parcels)
+            .map(|(label, &name)| (label.clone(), printer.parcel(&config.parcels[name])))
+            .collect(),
     };
 
-    Ok(())
+    let names = if multi {
+        cancellable(
+            MultiSelect::with_theme(&theme)
+                .with_prompt(t!("prompt-parcel"))
+                .items(&labels)
+                .interact_opt(),
+        )?
+        .unwrap_or_default()
+    } else {
+        cancellable(
+            FuzzySelect::with_theme(&theme)
+                .with_prompt(t!("prompt-parcel"))
+                .items(&labels)
+                .default(0)
+                // Highlighting splits the escape codes of the dimmed details
+                .highlight_matches(!color)
+                .interact_opt(),
+        )?
+        .into_iter()
+        .collect()
+    }
+    .into_iter()
+    .map(|i| parcels[i].as_str())
+    .collect::<Vec<_>>();
+
+    if names.is_empty() {
+        eprintln!("{}", t!("no-parcel-selected"));
+        return Ok(());
+    }
+    open_parcels(config, &names, opener)
 }
 
-pub fn choose_fzf(config_path: &Path, multi: bool) -> anyhow::Result<()> {
+#[cfg(feature = "dialog")]
+pub fn choose_entries(
+    config: &ParcelConfig,
+    name: &str,
+    opener: &dyn Opener,
+) -> anyhow::Result<()> {
+    use dialoguer::MultiSelect;
+
+    let (name, parcel) = find_parcel(config, name)?;
+    let entries = parcel.entries().collect::<Vec<_>>();
+    let theme = dialog_theme(&config.settings);
+
+    let selected = cancellable(
+        MultiSelect::with_theme(theme.as_ref())
+            .with_prompt(t!("prompt-entries", name = name))
+            .items(&entries)
+            .interact_opt(),
+    )?
+    .unwrap_or_default()
+    .into_iter()
+    .map(|i| entries[i].clone())
+    .collect();
+    open_entries(config, name, selected, opener)
+}
+
+pub fn choose_fzf(
+    config: &ParcelConfig,
+    config_path: &Path,
+    multi: bool,
+    opener: &dyn Opener,
+) -> anyhow::Result<()> {
     let current_exe = env::current_exe()?;
-    let config = ParcelConfig::load(config_path)?;
     let parcels = config.parcels.keys().collect::<Vec<_>>();
     if parcels.is_empty() {
-        eprintln!("No parcels available. Please add parcels to the configuration file.");
+        eprintln!("{}", t!("no-parcels"));
         return Ok(());
     }
 
@@ -82,8 +460,10 @@ pub fn choose_fzf(config_path: &Path, multi: bool) -> anyhow::Result<()> {
         "--bind=tab:down,shift-tab:up",
         "--cycle",
         "--no-sort",
-        "--ansi",
         "--tmux=center,70%,40%",
+        // Lines are `<name>\t<label>`, only the label is shown
+        "--delimiter=\t",
+        "--with-nth=2..",
     ];
     if multi {
         args.extend([
@@ -92,43 +472,308 @@ pub fn choose_fzf(config_path: &Path, multi: bool) -> anyhow::Result<()> {
             "--bind=space:toggle+down",
         ]);
     }
+    // fzf draws on the terminal even though its stdout is captured
+    let color = if output::use_color(&config.settings, &io::stderr()) {
+        args.push("--ansi");
+        "always"
+    } else {
+        args.push("--no-color");
+        "never"
+    };
+    // Previews are rendered up front, so that scrolling doesn't run the binary and reparse the config
+    // for every item. They are named after the index of their line, which fzf passes as `{n}`
+    let previews = env::temp_dir().join(format!("kozutsumi-previews-{}", process::id()));
+    fs::create_dir_all(&previews)?;
+    let printer = Printer::with_color(&config.settings, color == "always");
+    for (i, name) in parcels.iter().enumerate() {
+        fs::write(
+            previews.join(i.to_string()),
+            printer.parcel(&config.parcels[*name]),
+        )?;
+    }
+    // The previews are already styled, bat only adds highlighting when it is installed
+    let viewer = if command_exists("bat") {
+        format!("bat --color={} -pp", color)
+    } else {
+        "cat".to_string()
+    };
     let fzf = Command::new("fzf")
         .args(args)
-        .arg("--preview")
+        .args(&config.settings.fzf_args)
         .arg(format!(
-            "sh -c '{} --config {} list \"$1\" | bat --color=always -pp' sh {}",
+            "--bind=ctrl-e:become({} --config {} --color {} choose --chooser fzf --entries {})",
             current_exe.to_string_lossy(),
             config_path.as_os_str().to_string_lossy(),
-            "{}"
+            color,
+            "{1}"
         ))
+        .arg("--preview")
+        .arg(format!("{} '{}'/{{n}}", viewer, previews.display()))
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .spawn()?;
 
     let mut stdin = fzf.stdin.as_ref().unwrap();
-    for parcel in &parcels {
-        writeln!(stdin, "{}", parcel)?;
+    for name in &parcels {
+        let label = chooser_label(name, &config.parcels[*name], color == "always");
+        writeln!(stdin, "{}\t{}", name, label)?;
     }
 
-    let output = fzf.wait_with_output()?;
+    let output = fzf.wait_with_output();
+    let _ = fs::remove_dir_all(&previews);
+    let output = output?;
     if output.status.success() {
         let selection = String::from_utf8_lossy(&output.stdout);
-        let name = selection.trim().to_string();
-        if !name.is_empty() {
-            ParcelCommands::Open { name }.run(config_path.as_ref())?;
-        } else {
-            eprintln!("No parcel selected.");
+        let names = selection
+            .lines()
+            .filter_map(|line| line.split('\t').next())
+            .filter(|name| !name.is_empty())
+            .collect::<Vec<_>>();
+        if names.is_empty() {
+            eprintln!("{}", t!("no-parcel-selected"));
+            return Ok(());
         }
-        Ok(())
+        open_parcels(config, &names, opener)
     } else {
         match output.status.code() {
             Some(130) | Some(1) => {
                 // 130: User cancelled (Ctrl-C)
                 //   1: No match found
-                eprintln!("No parcel selected.");
+                eprintln!("{}", t!("no-parcel-selected"));
                 Ok(())
             }
-            _ => anyhow::bail!("fzf failed with status: {}", output.status),
+            _ => anyhow::bail!(t!("fzf-failed", status = output.status)),
         }
     }
 }
+
+/// Choose entries of a single parcel with fzf, and open only the selected ones
+pub fn choose_entries_fzf(
+    config: &ParcelConfig,
+    name: &str,
+    opener: &dyn Opener,
+) -> anyhow::Result<()> {
+    let (name, parcel) = find_parcel(config, name)?;
+    let entries = parcel.entries().collect::<Vec<_>>();
+
+    let mut args = vec![
+        "--multi",
+        "--layout=reverse",
+        "--bind=tab:down,shift-tab:up",
+        "--bind=ctrl-a:select-all",
+        "--bind=space:toggle+down",
+        "--cycle",
+        "--no-sort",
+        "--tmux=center,70%,40%",
+        // Lines are `<index>\t<entry>`, only the entry is shown
+        "--delimiter=\t",
+        "--with-nth=2..",
+    ];
+    args.push(if output::use_color(&config.settings, &io::stderr()) {
+        "--ansi"
+    } else {
+        "--no-color"
+    });
+    let fzf = Command::new("fzf")
+        .args(args)
+        .args(&config.settings.fzf_args)
+        .arg(format!("--prompt={}> ", name))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let mut stdin = fzf.stdin.as_ref().unwrap();
+    for (i, entry) in entries.iter().enumerate() {
+        writeln!(stdin, "{}\t{}", i, entry)?;
+    }
+
+    let output = fzf.wait_with_output()?;
+    if output.status.success() {
+        let selected = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.split('\t').next()?.parse::<usize>().ok())
+            .filter_map(|i| entries.get(i).map(|&entry| entry.clone()))
+            .collect();
+        open_entries(config, name, selected, opener)
+    } else {
+        match output.status.code() {
+            Some(130) | Some(1) => {
+                eprintln!("{}", t!("no-entries-selected"));
+                Ok(())
+            }
+            _ => anyhow::bail!(t!("fzf-failed", status = output.status)),
+        }
+    }
+}
+
+/// Picks the indices of items, given the prompt and whether several can be picked
+type Pick = fn(&str, &[String], bool) -> anyhow::Result<Vec<usize>>;
+
+/// The chooser to use when `--chooser` isn't passed: the first available one of `settings.chooser`,
+/// or of fzf, dialoguer, builtin, and gui when it is empty
+pub fn preferred_chooser(settings: &Settings) -> anyhow::Result<Chooser> {
+    let defaults = [
+        Chooser::Fzf,
+        #[cfg(feature = "dialog")]
+        Chooser::Dialoguer,
+        Chooser::Builtin,
+        Chooser::Gui,
+    ];
+    let choosers = if settings.chooser.is_empty() {
+        &defaults[..]
+    } else {
+        &settings.chooser
+    };
+    let terminal = io::stdin().is_terminal() && io::stderr().is_terminal();
+
+    choosers
+        .iter()
+        .copied()
+        .find(|chooser| match chooser {
+            Chooser::Fzf => terminal && command_exists("fzf"),
+            #[cfg(feature = "dialog")]
+            Chooser::Dialoguer => terminal,
+            Chooser::Builtin => terminal,
+            Chooser::Gui => true,
+        })
+        .ok_or_else(|| anyhow::anyhow!(t!("chooser-unavailable")))
+}
+
+/// Show a native list dialog through AppleScript, returning the indices of the chosen items.
+///
+/// The items are passed as arguments rather than written into the script, so they need no escaping.
+fn pick_gui(prompt: &str, items: &[String], multi: bool) -> anyhow::Result<Vec<usize>> {
+    const SCRIPT: &str = r#"on run argv
+    set promptText to item 1 of argv
+    set choices to items 3 thru -1 of argv
+    activate
+    if item 2 of argv is "multi" then
+        set picked to choose from list choices with prompt promptText with multiple selections allowed
+    else
+        set picked to choose from list choices with prompt promptText
+    end if
+    if picked is false then return ""
+    set AppleScript's text item delimiters to linefeed
+    return picked as text
+end run"#;
+
+    let output = Command::new("osascript")
+        .arg("-e")
+        .arg(SCRIPT)
+        .arg(prompt)
+        .arg(if multi { "multi" } else { "single" })
+        .args(items)
+        .output()?;
+    if !output.status.success() {
+        anyhow::bail!(t!(
+            "gui-chooser-failed",
+            reason = String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|picked| items.iter().position(|item| item == picked))
+        .collect())
+}
+
+/// Ask for the numbers of the items on stdin, separated by spaces or commas when several can be picked.
+/// Nothing is picked if nothing valid is typed
+fn pick_builtin(prompt: &str, items: &[String], multi: bool) -> anyhow::Result<Vec<usize>> {
+    eprintln!("{}", prompt);
+    for (i, item) in items.iter().enumerate() {
+        eprintln!("  {}) {}", i + 1, item);
+    }
+    if multi {
+        eprint!("{} ", t!("builtin-choose-many"));
+    } else {
+        eprint!("{} ", t!("builtin-choose"));
+    }
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+
+    let picked = answer
+        .split([' ', ','])
+        .filter_map(|n| n.trim().parse::<usize>().ok())
+        .filter_map(|n| n.checked_sub(1).filter(|&i| i < items.len()))
+        .take(if multi { items.len() } else { 1 })
+        .collect();
+    Ok(picked)
+}
+
+/// Choose parcels in a native dialog, for when there is no terminal to show a chooser in
+pub fn choose_gui(config: &ParcelConfig, multi: bool, opener: &dyn Opener) -> anyhow::Result<()> {
+    choose_picked(config, multi, pick_gui, opener)
+}
+
+/// Choose parcels by typing their numbers, which needs nothing but a terminal
+pub fn choose_builtin(
+    config: &ParcelConfig,
+    multi: bool,
+    opener: &dyn Opener,
+) -> anyhow::Result<()> {
+    choose_picked(config, multi, pick_builtin, opener)
+}
+
+fn choose_picked(
+    config: &ParcelConfig,
+    multi: bool,
+    pick: Pick,
+    opener: &dyn Opener,
+) -> anyhow::Result<()> {
+    let parcels = config.parcels.keys().collect::<Vec<_>>();
+    if parcels.is_empty() {
+        eprintln!("{}", t!("no-parcels"));
+        return Ok(());
+    }
+    let labels = parcels
+        .iter()
+        .map(|&name| chooser_label(name, &config.parcels[name], false))
+        .collect::<Vec<_>>();
+
+    let names = pick(&t!("prompt-parcel"), &labels, multi)?
+        .into_iter()
+        .map(|i| parcels[i].as_str())
+        .collect::<Vec<_>>();
+    if names.is_empty() {
+        eprintln!("{}", t!("no-parcel-selected"));
+        return Ok(());
+    }
+    open_parcels(config, &names, opener)
+}
+
+/// Choose entries of a single parcel in a native dialog, and open only the selected ones
+pub fn choose_entries_gui(
+    config: &ParcelConfig,
+    name: &str,
+    opener: &dyn Opener,
+) -> anyhow::Result<()> {
+    choose_entries_picked(config, name, pick_gui, opener)
+}
+
+/// Choose entries of a single parcel by typing their numbers, and open only the selected ones
+pub fn choose_entries_builtin(
+    config: &ParcelConfig,
+    name: &str,
+    opener: &dyn Opener,
+) -> anyhow::Result<()> {
+    choose_entries_picked(config, name, pick_builtin, opener)
+}
+
+fn choose_entries_picked(
+    config: &ParcelConfig,
+    name: &str,
+    pick: Pick,
+    opener: &dyn Opener,
+) -> anyhow::Result<()> {
+    let (name, parcel) = find_parcel(config, name)?;
+    let entries = parcel.entries().collect::<Vec<_>>();
+    let items = entries.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+    let selected = pick(&t!("prompt-entries", name = name), &items, true)?
+        .into_iter()
+        .map(|i| entries[i].clone())
+        .collect();
+    open_entries(config, name, selected, opener)
+}